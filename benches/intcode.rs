@@ -0,0 +1,97 @@
+//! Times the Intcode interpreter on some of the heavier puzzle inputs.
+//!
+//! Run with `cargo bench`.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::{Duration, Instant};
+use advent_2019::intcode_computer::{read_intcode_program, Event, IntcodeComputer};
+
+const NUM_RUNS: usize = 10;
+
+fn main() {
+    bench("day 9 (BOOST, part 2)", 9, day_9);
+    bench("day 19 (50x50 beam scan)", 19, day_19);
+    bench("day 23 (50 NICs, until first NAT packet)", 23, day_23);
+}
+
+fn bench(name: &str, day: u32, f: fn(&[i64]) -> i64) {
+    let path = format!("{}/inputs/{}", env!("CARGO_MANIFEST_DIR"), day);
+    let prog = read_intcode_program(BufReader::new(File::open(path).unwrap()));
+
+    let mut times: Vec<Duration> = (0..NUM_RUNS).map(|_| {
+        let start = Instant::now();
+        std::hint::black_box(f(&prog));
+        start.elapsed()
+    }).collect();
+    times.sort();
+
+    println!("{:<45} min {:>8.2?}   median {:>8.2?}   max {:>8.2?}",
+             name, times[0], times[NUM_RUNS / 2], times[NUM_RUNS - 1]);
+}
+
+fn day_9(prog: &[i64]) -> i64 {
+    let mut computer = IntcodeComputer::new(prog.to_vec());
+    computer.provide_input(2);
+
+    match computer.run_until_event().unwrap() {
+        Event::Output(x) => x,
+        event => panic!("Unexpected event: {:?}", event),
+    }
+}
+
+fn day_19(prog: &[i64]) -> i64 {
+    let start = IntcodeComputer::new(prog.to_vec());
+    let mut count = 0;
+
+    for i in 0..50 {
+        for j in 0..50 {
+            let mut drone = start.clone();
+            drone.provide_input(i);
+            drone.provide_input(j);
+
+            if drone.run_until_event().unwrap() == Event::Output(1) {
+                count += 1;
+            }
+        }
+    }
+
+    count
+}
+
+/// Round-robin over the NICs, one event at a time.
+fn day_23(prog: &[i64]) -> i64 {
+    let mut nics: Vec<_> = (0..50).map(|id| {
+        let mut nic = IntcodeComputer::new(prog.to_vec());
+        nic.provide_input(id);
+        (nic, VecDeque::new(), vec![])
+    }).collect();
+
+    loop {
+        for i in 0..nics.len() {
+            let (nic, inbox, outbox) = &mut nics[i];
+
+            match nic.run_until_event().unwrap() {
+                Event::NeedsInput => match inbox.pop_front() {
+                    Some((x, y)) => {
+                        nic.provide_input(x);
+                        nic.provide_input(y);
+                    }
+                    None => nic.provide_input(-1),
+                }
+                Event::Output(val) => outbox.push(val),
+                Event::Halted => panic!("NIC {} halted", i),
+            }
+
+            if let [addr, x, y] = outbox[..] {
+                outbox.clear();
+
+                if addr == 255 {
+                    return y;
+                }
+                nics[addr as usize].1.push_back((x, y));
+            }
+        }
+    }
+}
@@ -1,14 +1,40 @@
+use std::collections::VecDeque;
 use std::fmt;
 use std::io::BufRead;
+use std::time::Instant;
 use itertools::Itertools;
 use mem::Memory;
-use instructions::{Operation, ParameterMode, ParameterType};
+use limits::Limits;
+use counting::Counter;
+use instructions::{Opcode, ParameterType};
 use Operation::{Add, Mul, Input, Output, JumpIfTrue, JumpIfFalse, LessThan, Equals, AdjustRelBase, Halt};
 use ParameterType::{Read, Write};
 use ParameterMode::{Position, Immediate, Relative};
 
+pub use error::{IntcodeError, ErrorKind};
+pub use instructions::{Operation, ParameterMode};
+pub use disassemble::{disassemble, Instruction};
+pub use assemble::{assemble, AsmError};
+pub use debugger::debug;
+pub use trace::{Tracer, TraceRecord, MemoryWrite, NoTrace, InstructionCounter, TraceLog};
+pub use adapters::{input_from_iter, input_from_receiver, output_to_vec, output_to_sender, output_chunks};
+pub use ascii::{AsciiIo, AsciiEvent, AsciiError};
+pub use asynchronous::{LocalExecutor, async_channel, AsyncSender, AsyncReceiver};
+pub use counting::count_instructions;
+
 mod mem;
+mod limits;
 mod instructions;
+mod error;
+mod disassemble;
+mod assemble;
+mod debugger;
+mod trace;
+mod save;
+mod adapters;
+mod ascii;
+mod asynchronous;
+mod counting;
 
 pub fn read_intcode_program(input: impl BufRead) -> Vec<i64> {
     let (line,) = input.lines().map(Result::unwrap).collect_tuple().unwrap();
@@ -18,7 +44,9 @@ pub fn read_intcode_program(input: impl BufRead) -> Vec<i64> {
     }).collect()
 }
 
-pub struct IntcodeComputer<I=fn() -> i64, O=fn(i64)> {
+/// Cloning is cheap-ish (it copies the memory), as long as the I/O handlers and tracer are cheap to clone.
+#[derive(Clone)]
+pub struct IntcodeComputer<I=fn() -> Option<i64>, O=fn(i64), T=NoTrace> {
     /// Instruction pointer.
     ip: i64,
     /// Relative base.
@@ -26,8 +54,39 @@ pub struct IntcodeComputer<I=fn() -> i64, O=fn(i64)> {
 
     memory: Memory,
 
+    /// Values supplied via `provide_input`, not yet consumed by an input instruction.
+    pending_input: VecDeque<i64>,
+
     input: Option<I>,
     output: Option<O>,
+
+    tracer: T,
+
+    limits: Limits,
+    /// For `count_instructions`.
+    counter: Counter,
+}
+
+/// Everything about a computer's state, except for its I/O handlers and tracer.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Snapshot {
+    ip: i64,
+    rb: i64,
+    memory: Memory,
+    pending_input: VecDeque<i64>,
+}
+
+/// The reason `IntcodeComputer::run_until_event` returned control to the caller.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// The program wants to read a value, and none is pending.
+    ///
+    /// Call `provide_input` and then resume; the input instruction will be retried.
+    NeedsInput,
+    /// The program wrote a value.
+    Output(i64),
+    /// The program has halted. Resuming will just report this again.
+    Halted,
 }
 
 impl IntcodeComputer {
@@ -36,64 +95,316 @@ impl IntcodeComputer {
             ip: 0,
             rb: 0,
             memory: Memory::new(program),
+            pending_input: VecDeque::new(),
+            input: None,
+            output: None,
+            tracer: NoTrace,
+            limits: Limits::default(),
+            counter: Counter::new(),
+        }
+    }
+
+    /// Resume from a snapshot.
+    ///
+    /// Like `new`, there's no memory limit until `memory_limit` sets one, whatever computer the
+    /// snapshot came from.
+    pub fn from_snapshot(snapshot: Snapshot) -> Self {
+        let Snapshot { ip, rb, mut memory, pending_input } = snapshot;
+        memory.set_limit(None);
+
+        IntcodeComputer {
+            ip,
+            rb,
+            memory,
+            pending_input,
             input: None,
             output: None,
+            tracer: NoTrace,
+            limits: Limits::default(),
+            counter: Counter::new(),
         }
     }
+}
 
+impl<I, O, T> IntcodeComputer<I, O, T> {
     /// Set input and output.
-    pub fn io<I, O>(self, input: I, output: O) -> IntcodeComputer<I, O> {
+    ///
+    /// The input handler returns `None` when it has nothing left to give.
+    ///
+    /// See e.g. `input_from_iter` and `output_to_vec` for some common handlers.
+    pub fn io<I2, O2>(self, input: I2, output: O2) -> IntcodeComputer<I2, O2, T> {
         // The compiler doesn't like `IntcodeComputer { input, output, ..self }`,
         // otherwise we'd just write that.
         IntcodeComputer {
             ip: self.ip,
             rb: self.rb,
             memory: self.memory,
+            pending_input: self.pending_input,
             input: Some(input),
             output: Some(output),
+            tracer: self.tracer,
+            limits: self.limits,
+            counter: self.counter,
+        }
+    }
+
+    /// Save the current state, e.g. to fork several runs from the same point. See `restore`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            ip: self.ip,
+            rb: self.rb,
+            memory: self.memory.clone(),
+            pending_input: self.pending_input.clone(),
+        }
+    }
+
+    /// Return to a previously saved state.
+    ///
+    /// The snapshot needn't have come from this computer. I/O handlers, tracer and limits
+    /// are unaffected. The memory limit isn't checked against the snapshot's memory, only
+    /// against what gets allocated after it.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.ip = snapshot.ip;
+        self.rb = snapshot.rb;
+        self.memory.clone_from(&snapshot.memory);
+        self.pending_input.clone_from(&snapshot.pending_input);
+    }
+
+    /// Whether we're in exactly the state `snapshot` was taken in, i.e. restoring it would be a
+    /// no-op. Cheaper than comparing with a fresh `snapshot()`, since nothing gets copied.
+    pub fn is_in_state(&self, snapshot: &Snapshot) -> bool {
+        self.ip == snapshot.ip
+            && self.rb == snapshot.rb
+            && self.pending_input == snapshot.pending_input
+            && self.memory == snapshot.memory
+    }
+
+    /// Fail with `ErrorKind::MemoryLimitExceeded`, rather than allocate more than `words` words
+    /// of memory in total (including the program itself).
+    ///
+    /// Memory far beyond the program is allocated a page at a time, so this is a little coarse.
+    /// Memory that's already allocated, or that `restore` copies in, isn't checked.
+    pub fn memory_limit(mut self, words: usize) -> Self {
+        self.memory.set_limit(Some(words));
+        self
+    }
+
+    /// Fail with `ErrorKind::BudgetExhausted` once another `budget` instructions have been
+    /// executed, or never, if it's `None`.
+    ///
+    /// Every executed instruction uses up one unit. Input instructions waiting on input and
+    /// halts don't execute, so a blocked or halted computer keeps reporting that as usual,
+    /// whatever its budget. Running out leaves the computer at the next instruction, ready to
+    /// carry on once it's been given more budget.
+    pub fn set_budget(&mut self, budget: Option<u64>) {
+        self.limits.budget = budget;
+    }
+
+    /// What's left of the instruction budget.
+    pub fn budget(&self) -> Option<u64> {
+        self.limits.budget
+    }
+
+    /// Fail with `ErrorKind::DeadlineExceeded` once `deadline` has passed, or never, if it's `None`.
+    ///
+    /// The clock is only checked every thousand or so instructions. As with `set_budget`,
+    /// the computer can carry on afterwards, given a later deadline.
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.limits.set_deadline(deadline);
+    }
+
+    /// Report each executed instruction to `tracer`.
+    ///
+    /// Pass `&mut tracer` to be able to inspect it afterwards.
+    pub fn trace<T2: Tracer>(self, tracer: T2) -> IntcodeComputer<I, O, T2> {
+        IntcodeComputer {
+            ip: self.ip,
+            rb: self.rb,
+            memory: self.memory,
+            pending_input: self.pending_input,
+            input: self.input,
+            output: self.output,
+            tracer,
+            limits: self.limits,
+            counter: self.counter,
         }
     }
 }
 
-impl<I, O> IntcodeComputer<I, O>
+impl<I, O, T> IntcodeComputer<I, O, T>
 where
-    I: FnMut() -> i64,
+    I: FnMut() -> Option<i64>,
     O: FnMut(i64),
+    T: Tracer,
 {
-    pub fn run(mut self) {
-        self.run_inner();
+    pub fn run(mut self) -> Result<(), IntcodeError> {
+        self.resume()
+    }
+
+    pub fn run_noun_verb(mut self, noun: i64, verb: i64) -> Result<i64, IntcodeError> {
+        self.poke(1, noun)?;
+        self.poke(2, verb)?;
+
+        self.resume()?;
+
+        self.peek(0)
+    }
+
+    /// Run until halt, servicing input and output requests with the closures passed to `io`.
+    ///
+    /// Like `run`, but doesn't consume the computer, so it can carry on after a recoverable
+    /// error, e.g. the budget running out.
+    pub fn resume(&mut self) -> Result<(), IntcodeError> {
+        loop {
+            match self.run_until_event()? {
+                Event::NeedsInput => {
+                    let x = match self.input.as_mut() {
+                        Some(input) => input(),
+                        None => return Err(self.fault(ErrorKind::MissingIoHandler)),
+                    };
+                    let x = x.ok_or_else(|| self.fault(ErrorKind::InputExhausted))?;
+                    self.provide_input(x);
+                }
+                Event::Output(x) => {
+                    match self.output.as_mut() {
+                        Some(output) => output(x),
+                        None => return Err(self.fault(ErrorKind::MissingIoHandler)),
+                    }
+                }
+                Event::Halted => {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl<I, O, T: Tracer> IntcodeComputer<I, O, T> {
+    /// Instruction pointer.
+    pub fn ip(&self) -> i64 {
+        self.ip
+    }
+
+    /// Relative base.
+    pub fn rb(&self) -> i64 {
+        self.rb
     }
 
-    pub fn run_noun_verb(mut self, noun: i64, verb: i64) -> i64 {
-        self.memory[1] = noun;
-        self.memory[2] = verb;
+    /// Read a word of memory.
+    pub fn peek(&self, addr: i64) -> Result<i64, IntcodeError> {
+        self.memory.get(addr).map_err(|kind| self.fault(kind))
+    }
+
+    /// Overwrite a word of memory.
+    pub fn poke(&mut self, addr: i64, value: i64) -> Result<(), IntcodeError> {
+        self.memory.set(addr, value).map_err(|kind| self.fault(kind))
+    }
+
+    /// Decode (without executing) the instruction at the instruction pointer.
+    ///
+    /// Returns None if it isn't a valid instruction.
+    pub fn current_instruction(&self) -> Option<Instruction> {
+        let mut words = [0; 4];
 
-        self.run_inner();
+        for (i, word) in words.iter_mut().enumerate() {
+            *word = self.memory.get(self.ip + i as i64).ok()?;
+        }
 
-        self.memory[0]
+        Instruction::decode(&words)
     }
 
-    fn run_inner(&mut self) {
-        while self.step() {}
+    /// Queue up a value for the program's next input instruction.
+    ///
+    /// Values are consumed in the order they're provided.
+    pub fn provide_input(&mut self, x: i64) {
+        self.pending_input.push_back(x);
+    }
+
+    /// Execute instructions until the program produces output, blocks on input, or halts.
+    ///
+    /// Unlike `run`, this doesn't consume the computer, so it can be paused and resumed freely.
+    pub fn run_until_event(&mut self) -> Result<Event, IntcodeError> {
+        loop {
+            if let Some(event) = self.step()? {
+                return Ok(event);
+            }
+        }
     }
 
     /// Execute the current instruction and bump the program counter.
-    /// Return false if the cpu should halt.
-    fn step(&mut self) -> bool {
-        let (op, args) = self.read_instruction();
+    ///
+    /// Returns an event if the caller needs to intervene. On `NeedsInput` and `Halted`,
+    /// the instruction pointer is left pointing at the current instruction.
+    ///
+    /// On error, nothing is modified.
+    #[inline]
+    pub fn step(&mut self) -> Result<Option<Event>, IntcodeError> {
+        let limited = self.limits.is_active();
+        if limited {
+            if let Err(kind) = self.limits.check() {
+                // Nothing would be executed anyway.
+                return match self.blocked_event() {
+                    Some(event) => Ok(Some(event)),
+                    None => Err(self.fault(kind)),
+                };
+            }
+        }
+
+        let start = self.ip;
+
+        let event = self.execute(start).map_err(|kind| {
+            self.ip = start;
+            self.fault(kind)
+        })?;
+
+        if !matches!(event, Some(Event::NeedsInput | Event::Halted)) {
+            self.counter.incr();
+            if limited {
+                self.limits.use_one();
+            }
+        }
+
+        Ok(event)
+    }
+
+    /// If the current instruction is a halt, or an input with none pending, the event that
+    /// `step` would return without executing anything.
+    fn blocked_event(&mut self) -> Option<Event> {
+        match self.memory.opcode(self.ip).ok()?.op {
+            Halt => Some(Event::Halted),
+            Input if self.pending_input.is_empty() => Some(Event::NeedsInput),
+            _ => None,
+        }
+    }
+
+    /// Helper for `step`. Any writes to memory happen last, after all possible errors.
+    fn execute(&mut self, start: i64) -> Result<Option<Event>, ErrorKind> {
+        let rb = self.rb;
+        let (op, args) = self.read_instruction()?;
+
+        let mut event = None;
+        let mut write = None;
 
         match op {
             Add => {
-                self.memory[args[2]] = args[0] + args[1];
+                write = Some((args[2], args[0] + args[1]));
             }
             Mul => {
-                self.memory[args[2]] = args[0] * args[1];
+                write = Some((args[2], args[0] * args[1]));
             }
             Input => {
-                self.memory[args[0]] = self.input.as_mut().unwrap()();
+                match self.pending_input.front() {
+                    Some(&x) => {
+                        write = Some((args[0], x));
+                    }
+                    None => {
+                        self.ip = start;
+                        return Ok(Some(Event::NeedsInput));
+                    }
+                }
             }
             Output => {
-                self.output.as_mut().unwrap()(args[0]);
+                event = Some(Event::Output(args[0]));
             }
             JumpIfTrue => {
                 if args[0] != 0 {
@@ -106,58 +417,258 @@ where
                 }
             }
             LessThan => {
-                self.memory[args[2]] = (args[0] < args[1]) as i64;
+                write = Some((args[2], (args[0] < args[1]) as i64));
             }
             Equals => {
-                self.memory[args[2]] = (args[0] == args[1]) as i64;
+                write = Some((args[2], (args[0] == args[1]) as i64));
             }
             AdjustRelBase => {
                 self.rb += args[0];
             }
             Halt => {
-                return false;
+                self.ip = start;
+                event = Some(Event::Halted);
+            }
+        }
+
+        let mut trace_write = None;
+
+        if let Some((addr, new)) = write {
+            if T::ENABLED {
+                let old = self.memory.get(addr)?;
+                trace_write = Some(MemoryWrite { addr, old, new });
             }
+
+            self.memory.set(addr, new)?;
+        }
+
+        if op == Input {
+            self.pending_input.pop_front();
+        }
+
+        if T::ENABLED {
+            let args = &args[..op.param_types().len()];
+            self.tracer.trace(&TraceRecord { ip: start, rb, op, args, write: trace_write });
         }
 
-        true
+        Ok(event)
     }
 
-    /// Returns an operation and its list of arguments.
+    /// Returns an operation and its arguments. Only the first `op.param_types().len()`
+    /// arguments are meaningful.
     ///
     /// Read-parameter arguments are values, and write-parameter arguments are addresses.
     ///
     /// Updates the instruction pointer accordingly.
-    fn read_instruction(&mut self) -> (Operation, Vec<i64>) {
-        let opcode = self.memory[self.ip];
+    fn read_instruction(&mut self) -> Result<(Operation, [i64; 3]), ErrorKind> {
+        let Opcode { op, modes } = self.memory.opcode(self.ip)?;
         self.ip += 1;
 
-        let op = Operation::new(opcode);
-        let param_types = op.param_types().into_iter();
-        let param_modes = ParameterMode::parse_opcode(opcode);
+        let mut args = [0; 3];
 
-        let args = param_types.zip(param_modes).map(|(type_, mode)| {
-            let a = self.memory[self.ip];
+        for (i, &type_) in op.param_types().iter().enumerate() {
+            let a = self.memory.get(self.ip)?;
             self.ip += 1;
 
-            match (type_, mode) {
-                (Read, Position) => self.memory[a],
-                (Read, Relative) => self.memory[a + self.rb],
+            args[i] = match (type_, modes[i]) {
+                (Read, Position) => self.memory.get(a)?,
+                (Read, Relative) => self.memory.get(a + self.rb)?,
                 (Read, Immediate) => a,
 
                 (Write, Position) => a,
                 (Write, Relative) => a + self.rb,
-                (Write, Immediate) => {
-                    panic!("Write params can't be in immediate mode: {}", opcode)
-                }
-            }
-        }).collect();
+                (Write, Immediate) => unreachable!("Rejected by Opcode::decode"),
+            };
+        }
+
+        Ok((op, args))
+    }
 
-        (op, args)
+    /// Tag an error with the machine's current registers.
+    fn fault(&self, kind: ErrorKind) -> IntcodeError {
+        IntcodeError { kind, ip: self.ip, rb: self.rb }
     }
 }
 
-impl<I, O> fmt::Debug for IntcodeComputer<I, O> {
+impl<I, O, T> fmt::Debug for IntcodeComputer<I, O, T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "ip={} rb={} mem={:?}", self.ip, self.rb, self.memory)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_until_event() {
+        // Read two numbers, and output their sum.
+        let prog = vec![3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+        let mut computer = IntcodeComputer::new(prog);
+
+        assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+        // Asking again without providing anything shouldn't make progress.
+        assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+        computer.provide_input(3);
+
+        assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+        computer.provide_input(4);
+
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(7)));
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        // Output a running total of the inputs.
+        let prog = vec![3, 11, 1, 11, 12, 12, 4, 12, 1105, 1, 0, 0, 0];
+        let mut computer = IntcodeComputer::new(prog);
+
+        computer.provide_input(1);
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(1)));
+        let snapshot = computer.snapshot();
+
+        let mut fork = computer.clone();
+        fork.provide_input(10);
+        assert_eq!(fork.run_until_event(), Ok(Event::Output(11)));
+
+        computer.provide_input(2);
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(3)));
+        assert!(!computer.is_in_state(&snapshot));
+
+        computer.restore(&snapshot);
+        assert!(computer.is_in_state(&snapshot));
+        computer.provide_input(2);
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(3)));
+
+        let mut resumed = IntcodeComputer::from_snapshot(snapshot);
+        resumed.provide_input(5);
+        assert_eq!(resumed.run_until_event(), Ok(Event::Output(6)));
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Output 1, overwrite that instruction with a halt, and jump back to it.
+        let prog = vec![104, 1, 1101, 98, 1, 0, 1105, 1, 0];
+        let mut computer = IntcodeComputer::new(prog);
+
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(1)));
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+        assert_eq!(computer.ip(), 0);
+    }
+
+    #[test]
+    fn test_errors() {
+        use ErrorKind::*;
+
+        for (prog, kind, ip) in [
+            (vec![1, 0, 0, 0, 42], InvalidOpcode(42), 4),
+            (vec![-1], InvalidOpcode(-1), 0),
+            (vec![301, 0, 0, 0], InvalidParameterMode { opcode: 301, digit: 3 }, 0),
+            (vec![11101, 1, 1, 0], WriteInImmediateMode(11101), 0),
+            (vec![109, -5, 204, 0], NegativeAddress(-5), 2),
+            (vec![1, -3, 0, 0], NegativeAddress(-3), 0),
+            (vec![3, 0, 99], MissingIoHandler, 0),
+            // The output instruction has already completed by the time we go looking for a handler.
+            (vec![104, 1, 99], MissingIoHandler, 2),
+        ] {
+            let err = IntcodeComputer::new(prog).run().unwrap_err();
+            assert_eq!(err.kind, kind);
+            assert_eq!(err.ip, ip);
+        }
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        // Write 2 to a far-away address.
+        let prog = vec![1101, 1, 1, 1_000_000_000_000, 99];
+
+        let mut computer = IntcodeComputer::new(prog.clone());
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+        assert_eq!(computer.peek(1_000_000_000_000), Ok(2));
+
+        let err = IntcodeComputer::new(prog).memory_limit(1000).run().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::MemoryLimitExceeded(1_000_000_000_000));
+        assert_eq!(err.ip, 0);
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut outputs = vec![];
+
+        // Output 1, 2, 3.
+        let prog = vec![104, 1, 104, 2, 104, 3, 99];
+        let mut computer = IntcodeComputer::new(prog).io(|| None, |x| outputs.push(x));
+
+        computer.set_budget(Some(2));
+        let err = computer.resume().unwrap_err();
+        assert_eq!(err.kind, ErrorKind::BudgetExhausted);
+        assert_eq!(err.ip, 4);
+        assert_eq!(computer.budget(), Some(0));
+
+        computer.set_budget(None);
+        computer.resume().unwrap();
+        drop(computer);
+        assert_eq!(outputs, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_budget_when_blocked() {
+        // Input, then output it.
+        let mut computer = IntcodeComputer::new(vec![3, 5, 104, 9, 99, 0]);
+        computer.set_budget(Some(2));
+
+        for _ in 0..3 {
+            assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+        }
+        assert_eq!(computer.budget(), Some(2));
+
+        computer.provide_input(1);
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(9)));
+        assert_eq!(computer.budget(), Some(0));
+
+        // Out of budget, but halting doesn't need any.
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+        assert_eq!(computer.run_until_event(), Ok(Event::Halted));
+    }
+
+    #[test]
+    fn test_budget_on_error() {
+        // An instruction that fails doesn't use up any budget.
+        let mut computer = IntcodeComputer::new(vec![104, 1, 42]);
+        computer.set_budget(Some(5));
+
+        assert_eq!(computer.run_until_event(), Ok(Event::Output(1)));
+        assert_eq!(computer.run_until_event().unwrap_err().kind, ErrorKind::InvalidOpcode(42));
+        assert_eq!(computer.budget(), Some(4));
+    }
+
+    #[test]
+    fn test_deadline() {
+        // Loop forever.
+        let mut computer = IntcodeComputer::new(vec![1105, 1, 0]);
+
+        computer.set_deadline(Some(Instant::now()));
+        assert_eq!(computer.run_until_event().unwrap_err().kind, ErrorKind::DeadlineExceeded);
+        assert_eq!(computer.run_until_event().unwrap_err().kind, ErrorKind::DeadlineExceeded);
+
+        computer.set_deadline(None);
+        computer.set_budget(Some(10_000));
+        assert_eq!(computer.run_until_event().unwrap_err().kind, ErrorKind::BudgetExhausted);
+    }
+
+    #[test]
+    fn test_input_exhausted() {
+        let mut input = [7].into_iter();
+        let mut outputs = vec![];
+
+        // Echo input forever.
+        let prog = vec![3, 7, 4, 7, 1105, 1, 0, 0];
+        let err = IntcodeComputer::new(prog).io(|| input.next(), |x| outputs.push(x)).run().unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InputExhausted);
+        assert_eq!(err.ip, 0);
+        assert_eq!(outputs, [7]);
+    }
+}
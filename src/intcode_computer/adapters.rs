@@ -0,0 +1,95 @@
+//! Ready-made input and output handlers, for use with `IntcodeComputer::io`.
+
+use std::sync::mpsc::{Receiver, Sender};
+
+/// Input from an iterator (or anything that can become one). Runs out when the iterator does.
+pub fn input_from_iter(values: impl IntoIterator<Item=i64>) -> impl FnMut() -> Option<i64> {
+    let mut values = values.into_iter();
+
+    move || values.next()
+}
+
+/// Input from a channel. Blocks until a value arrives, and runs out once every sender has hung up.
+pub fn input_from_receiver(receiver: Receiver<i64>) -> impl FnMut() -> Option<i64> {
+    move || receiver.recv().ok()
+}
+
+/// Output appended to a vector.
+pub fn output_to_vec(values: &mut Vec<i64>) -> impl FnMut(i64) + '_ {
+    move |x| values.push(x)
+}
+
+/// Output sent down a channel. Anything sent after the receiver has hung up is dropped.
+pub fn output_to_sender(sender: Sender<i64>) -> impl FnMut(i64) {
+    move |x| {
+        let _ = sender.send(x);
+    }
+}
+
+/// Collect output into groups of `N` values, e.g. network packets or coordinates, and pass
+/// each complete group on to `f`.
+///
+/// If the program stops partway through a group, those values are never passed on.
+pub fn output_chunks<const N: usize>(mut f: impl FnMut([i64; N])) -> impl FnMut(i64) {
+    let mut chunk = [0; N];
+    let mut len = 0;
+
+    move |x| {
+        chunk[len] = x;
+        len += 1;
+
+        if len == N {
+            f(chunk);
+            len = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+    use super::*;
+    use crate::intcode_computer::{ErrorKind, IntcodeComputer};
+
+    /// Output each input, doubled, until the input runs out.
+    fn doubler() -> IntcodeComputer {
+        IntcodeComputer::new(vec![3, 11, 1002, 11, 2, 11, 4, 11, 1105, 1, 0, 0])
+    }
+
+    #[test]
+    fn test_iter_and_vec() {
+        let mut outputs = vec![];
+        let err = doubler().io(input_from_iter([1, 2, 3]), output_to_vec(&mut outputs)).run().unwrap_err();
+
+        assert_eq!(err.kind, ErrorKind::InputExhausted);
+        assert_eq!(outputs, [2, 4, 6]);
+    }
+
+    #[test]
+    fn test_channels() {
+        let (in_tx, in_rx) = mpsc::channel();
+        let (out_tx, out_rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            doubler().io(input_from_receiver(in_rx), output_to_sender(out_tx)).run()
+        });
+
+        in_tx.send(5).unwrap();
+        assert_eq!(out_rx.recv(), Ok(10));
+        in_tx.send(-1).unwrap();
+        assert_eq!(out_rx.recv(), Ok(-2));
+
+        drop(in_tx);
+        assert!(handle.join().unwrap().is_err());
+        assert!(out_rx.recv().is_err());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut pairs = vec![];
+        let _ = doubler().io(input_from_iter(1..=5), output_chunks(|[a, b]| pairs.push((a, b)))).run();
+
+        assert_eq!(pairs, [(2, 4), (6, 8)]);
+    }
+}
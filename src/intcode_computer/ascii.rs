@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::{error, fmt, io};
+use std::io::{BufRead, Write};
+use crate::intcode_computer::{ErrorKind, Event, IntcodeComputer, IntcodeError, Tracer};
+
+/// Text-based I/O for programs that talk in ASCII.
+///
+/// Input is read a line at a time from `input`, and ASCII output is written to `output`.
+/// Anything else the program outputs (usually the answer) is handed back as an `AsciiEvent`.
+pub struct AsciiIo<R, W> {
+    input: R,
+    output: W,
+    /// The rest of the current input line.
+    line: VecDeque<u8>,
+}
+
+/// Something that needs the caller's attention.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AsciiEvent {
+    /// The program output a value that isn't an ASCII character.
+    Value(i64),
+    Halted,
+}
+
+#[derive(Debug)]
+pub enum AsciiError {
+    Intcode(IntcodeError),
+    Io(io::Error),
+}
+
+impl<'a, W: Write> AsciiIo<&'a [u8], W> {
+    /// Feed the program a fixed script, e.g. `"NOT A J\nWALK\n"`.
+    pub fn scripted(script: &'a str, output: W) -> Self {
+        Self::new(script.as_bytes(), output)
+    }
+}
+
+impl<R: BufRead, W: Write> AsciiIo<R, W> {
+    pub fn new(input: R, output: W) -> Self {
+        Self { input, output, line: VecDeque::new() }
+    }
+
+    /// Run the program until it halts, returning any non-ASCII values it output.
+    pub fn run<I, O, T: Tracer>(&mut self, computer: &mut IntcodeComputer<I, O, T>) -> Result<Vec<i64>, AsciiError> {
+        let mut values = vec![];
+
+        while let AsciiEvent::Value(x) = self.next_event(computer)? {
+            values.push(x);
+        }
+
+        Ok(values)
+    }
+
+    /// Run the program until it outputs a non-ASCII value, or halts.
+    ///
+    /// Fails with `ErrorKind::InputExhausted` if the program wants more input than there is.
+    pub fn next_event<I, O, T: Tracer>(&mut self, computer: &mut IntcodeComputer<I, O, T>) -> Result<AsciiEvent, AsciiError> {
+        loop {
+            match computer.run_until_event()? {
+                Event::NeedsInput => {
+                    if self.line.is_empty() && !self.read_line()? {
+                        let kind = ErrorKind::InputExhausted;
+                        return Err(IntcodeError { kind, ip: computer.ip(), rb: computer.rb() }.into());
+                    }
+
+                    computer.provide_input(self.line.pop_front().unwrap() as i64);
+                }
+                Event::Output(x) if 0 <= x && x < 128 => {
+                    self.output.write_all(&[x as u8])?;
+                }
+                Event::Output(x) => {
+                    self.output.flush()?;
+                    return Ok(AsciiEvent::Value(x));
+                }
+                Event::Halted => {
+                    self.output.flush()?;
+                    return Ok(AsciiEvent::Halted);
+                }
+            }
+        }
+    }
+
+    /// Returns false at the end of the input.
+    fn read_line(&mut self) -> io::Result<bool> {
+        // Make sure any prompt is visible before we wait on the user.
+        self.output.flush()?;
+
+        let mut buf = String::new();
+        self.input.read_line(&mut buf)?;
+
+        if !buf.is_ascii() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Not ASCII: {:?}", buf)));
+        }
+
+        self.line.extend(buf.bytes());
+        Ok(!buf.is_empty())
+    }
+}
+
+impl From<IntcodeError> for AsciiError {
+    fn from(e: IntcodeError) -> Self {
+        Self::Intcode(e)
+    }
+}
+
+impl From<io::Error> for AsciiError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl fmt::Display for AsciiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Intcode(e) => write!(f, "{}", e),
+            Self::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl error::Error for AsciiError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode_computer::assemble;
+
+    #[test]
+    fn test_ascii_io() {
+        // Print a prompt, then echo one line of input in upper case, and output its length.
+        let prog = assemble("
+                    out #62
+                    out #32
+            loop:   in [c]
+                    eq [c], #10, [done]
+                    jt [done], #end
+                    lt [c], #97, [lower]
+                    jt [lower], #skip
+                    add [c], #-32, [c]
+            skip:   out [c]
+                    add [len], #1, [len]
+                    jt #1, #loop
+            end:    out #10
+                    out [len]
+                    hlt
+            c:      data 0
+            done:   data 0
+            lower:  data 0
+            len:    data 1000
+        ").unwrap();
+
+        let mut output = vec![];
+        let mut ascii = AsciiIo::scripted("Hi there\nignored\n", &mut output);
+        let values = ascii.run(&mut IntcodeComputer::new(prog.clone())).unwrap();
+
+        assert_eq!(values, [1008]);
+        assert_eq!(String::from_utf8(output).unwrap(), "> HI THERE\n");
+
+        let mut ascii = AsciiIo::scripted("no newline", io::sink());
+        match ascii.run(&mut IntcodeComputer::new(prog)) {
+            Err(AsciiError::Intcode(e)) => assert_eq!(e.kind, ErrorKind::InputExhausted),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::HashMap;
+use std::{error, fmt};
+use crate::intcode_computer::instructions::{Operation, ParameterMode, ParameterType};
+use ParameterMode::{Position, Immediate, Relative};
+
+/// Assemble a program written in the same syntax that `disassemble` produces, e.g.:
+///
+/// ```text
+/// ; Echo input until it's 0.
+/// loop:   in [x]
+///         out [x]
+///         jt [x], #loop
+///         hlt
+/// x:      data 0
+/// ```
+///
+/// Operands are `[addr]` for position mode, `#value` for immediate mode, and `rb+offset` for
+/// relative mode (or `rb-offset`, or just `rb`; square brackets around these are optional).
+///
+/// Besides instructions, a line may contain:
+/// * labels, `name:`, which can be used in place of any number, optionally with an offset (`name+2`);
+/// * `data` (or `db`) directives, taking a list of numbers, labels, and double-quoted strings;
+/// * comments, starting with `;`;
+/// * address prefixes, `12:`, which must match the address of whatever follows them.
+///
+/// The latter means that the output of `disassemble` can be assembled back into the original program.
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    // First pass: parse each line, and find out where the labels point.
+    let mut labels = HashMap::new();
+    let mut statements = vec![];
+    let mut addr = 0;
+
+    for (i, line) in source.lines().enumerate() {
+        let line_num = i + 1;
+        let error = |message| AsmError { line: line_num, message };
+
+        let mut rest = strip_comment(line).trim();
+
+        while let Some((prefix, after)) = split_label(rest) {
+            if let Ok(n) = prefix.parse::<usize>() {
+                if n != addr {
+                    return Err(error(format!("Address prefix {} doesn't match actual address {}", n, addr)));
+                }
+            } else if prefix == "rb" {
+                return Err(error("`rb` can't be used as a label".to_string()));
+            } else if labels.insert(prefix.to_string(), addr as i64).is_some() {
+                return Err(error(format!("Duplicate label: {}", prefix)));
+            }
+
+            rest = after.trim();
+        }
+
+        if rest.is_empty() {
+            continue;
+        }
+
+        let stmt = Statement::parse(rest).map_err(error)?;
+        addr += stmt.num_words();
+        statements.push((line_num, stmt));
+    }
+
+    // Second pass: resolve labels, and encode each statement.
+    let mut program = Vec::with_capacity(addr);
+
+    for (line, stmt) in statements {
+        stmt.encode(&labels, &mut program).map_err(|message| AsmError { line, message })?;
+    }
+
+    Ok(program)
+}
+
+/// A problem with a specific line of assembly source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsmError {
+    /// Line numbers start from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl error::Error for AsmError {}
+
+enum Statement {
+    Instruction(Operation, Vec<(ParameterMode, Expr)>),
+    Data(Vec<Expr>),
+}
+
+/// A number, or a reference to a label plus some offset.
+enum Expr {
+    Number(i64),
+    Label(String, i64),
+}
+
+impl Statement {
+    fn parse(s: &str) -> Result<Self, String> {
+        let (mnemonic, operands) = match s.split_once(char::is_whitespace) {
+            Some((mnemonic, operands)) => (mnemonic, split_operands(operands.trim())),
+            None => (s, vec![]),
+        };
+
+        if mnemonic == "data" || mnemonic == "db" {
+            let mut words = vec![];
+
+            for item in operands {
+                if item.starts_with('"') {
+                    words.extend(parse_string(item)?.chars().map(|c| Expr::Number(c as i64)));
+                } else {
+                    words.push(Expr::parse(item)?);
+                }
+            }
+
+            return Ok(Self::Data(words));
+        }
+
+        let op = Operation::from_mnemonic(mnemonic).ok_or_else(|| format!("Unknown mnemonic: {}", mnemonic))?;
+
+        let param_types = op.param_types();
+        if operands.len() != param_types.len() {
+            return Err(format!("{} takes {} operands, got {}", mnemonic, param_types.len(), operands.len()));
+        }
+
+        let params = param_types.iter().zip(operands).map(|(&type_, operand)| {
+            let (mode, expr) = parse_operand(operand)?;
+
+            if type_ == ParameterType::Write && mode == Immediate {
+                Err(format!("Can't write to an immediate operand: {}", operand))
+            } else {
+                Ok((mode, expr))
+            }
+        }).collect::<Result<_, _>>()?;
+
+        Ok(Self::Instruction(op, params))
+    }
+
+    fn num_words(&self) -> usize {
+        match self {
+            Self::Instruction(_, params) => 1 + params.len(),
+            Self::Data(words) => words.len(),
+        }
+    }
+
+    fn encode(&self, labels: &HashMap<String, i64>, program: &mut Vec<i64>) -> Result<(), String> {
+        match self {
+            Self::Instruction(op, params) => {
+                let mut opcode = op.code();
+                let mut place_value = 100;
+
+                for (mode, _) in params {
+                    opcode += mode.digit() * place_value;
+                    place_value *= 10;
+                }
+
+                program.push(opcode);
+
+                for (_, expr) in params {
+                    program.push(expr.resolve(labels)?);
+                }
+            }
+            Self::Data(words) => {
+                for expr in words {
+                    program.push(expr.resolve(labels)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Expr {
+    /// E.g. `-12`, `loop`, `x+3`.
+    fn parse(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+
+        if let Ok(n) = s.parse() {
+            return Ok(Self::Number(n));
+        }
+
+        // Split off the offset, if any. (Skip the first char so we don't split a leading sign.)
+        let (name, offset) = match s.char_indices().skip(1).find(|&(_, c)| c == '+' || c == '-') {
+            Some((i, _)) => {
+                let offset = s[i..].parse().map_err(|_| format!("Invalid offset: {}", s))?;
+                (s[..i].trim(), offset)
+            }
+            None => (s, 0),
+        };
+
+        if !is_identifier(name) {
+            return Err(format!("Not a number or label: {}", s));
+        }
+
+        Ok(Self::Label(name.to_string(), offset))
+    }
+
+    fn resolve(&self, labels: &HashMap<String, i64>) -> Result<i64, String> {
+        match self {
+            Self::Number(n) => Ok(*n),
+            Self::Label(name, offset) => match labels.get(name) {
+                Some(addr) => Ok(addr + offset),
+                None => Err(format!("Undefined label: {}", name)),
+            }
+        }
+    }
+}
+
+/// E.g. `[12]`, `#-1`, `rb+3`.
+fn parse_operand(s: &str) -> Result<(ParameterMode, Expr), String> {
+    if let Some(value) = s.strip_prefix('#') {
+        return Ok((Immediate, Expr::parse(value)?));
+    }
+
+    let inner = s.strip_prefix('[').and_then(|s| s.strip_suffix(']'));
+
+    if let Some(offset) = inner.unwrap_or(s).trim().strip_prefix("rb") {
+        let offset = offset.trim();
+
+        // Careful not to treat a label like `rbx` as relative mode.
+        if offset.is_empty() {
+            return Ok((Relative, Expr::Number(0)));
+        } else if offset.starts_with('+') || offset.starts_with('-') {
+            let n = offset.replace(' ', "").parse().map_err(|_| format!("Invalid relative offset: {}", s))?;
+            return Ok((Relative, Expr::Number(n)));
+        }
+    }
+
+    match inner {
+        Some(addr) => Ok((Position, Expr::parse(addr)?)),
+        None => Err(format!("Invalid operand (expected `[addr]`, `#value`, or `rb+offset`): {}", s)),
+    }
+}
+
+/// Handles the escapes `\n`, `\t`, `\"`, and `\\`.
+fn parse_string(s: &str) -> Result<String, String> {
+    let inner = s.strip_prefix('"').and_then(|s| s.strip_suffix('"'))
+        .ok_or_else(|| format!("Unterminated string: {}", s))?;
+
+    let mut ret = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('t') => ret.push('\t'),
+            Some('"') => ret.push('"'),
+            Some('\\') => ret.push('\\'),
+            _ => return Err(format!("Invalid escape sequence in string: {}", s)),
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Split `name: rest` into its two halves, if the line starts with a label or an address prefix.
+fn split_label(line: &str) -> Option<(&str, &str)> {
+    let (prefix, rest) = line.split_once(':')?;
+    let prefix = prefix.trim();
+
+    if is_identifier(prefix) || prefix.parse::<usize>().is_ok() {
+        Some((prefix, rest))
+    } else {
+        None
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Remove everything after the first `;` that isn't inside a string.
+fn strip_comment(line: &str) -> &str {
+    match find_unquoted(line, ';').next() {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+/// Split on commas that aren't inside strings.
+fn split_operands(s: &str) -> Vec<&str> {
+    if s.is_empty() {
+        return vec![];
+    }
+
+    let mut ret = vec![];
+    let mut start = 0;
+
+    for i in find_unquoted(s, ',') {
+        ret.push(s[start..i].trim());
+        start = i + 1;
+    }
+    ret.push(s[start..].trim());
+
+    ret
+}
+
+/// Byte indices of `target` in `s`, skipping over double-quoted strings.
+fn find_unquoted(s: &str, target: char) -> impl Iterator<Item=usize> + '_ {
+    let mut in_string = false;
+    let mut escaped = false;
+
+    s.char_indices().filter_map(move |(i, c)| {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            None
+        } else if c == '"' {
+            in_string = true;
+            None
+        } else if c == target {
+            Some(i)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use crate::intcode_computer::{disassemble, read_intcode_program, IntcodeComputer};
+    use super::*;
+
+    #[test]
+    fn test_assemble() {
+        let source = r#"
+            ; Echo input until it's 0, then print the first char of a message.
+                    arb #x          ; Point rb at x, so that `rb` means the same as [x].
+            loop:   in [x]
+                    out [rb]
+                    jt rb+0, #loop
+                    out [msg]
+                    hlt
+            x:      data 0
+            msg:    db "hi;\n", msg+1
+        "#;
+
+        let prog = assemble(source).unwrap();
+        assert_eq!(&prog[..6], [109, 12, 3, 12, 204, 0]);
+        assert_eq!(&prog[12..], [0, 104, 105, 59, 10, 14]);
+
+        let mut input = [5, 7, 0].into_iter();
+        let mut output = vec![];
+        IntcodeComputer::new(prog).io(|| input.next(), |x| output.push(x)).run().unwrap();
+
+        assert_eq!(output, [5, 7, 0, 104]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // Every day whose input is an Intcode program.
+        for day in [2, 5, 7, 9, 11, 13, 15, 17, 19, 21, 23, 25] {
+            let path = format!("{}/inputs/{}", env!("CARGO_MANIFEST_DIR"), day);
+            let prog = read_intcode_program(BufReader::new(File::open(path).unwrap()));
+
+            assert_eq!(assemble(&disassemble(&prog)), Ok(prog), "day {}", day);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        for (source, line, message) in [
+            ("hlt\njt #1, #nowhere", 2, "Undefined label: nowhere"),
+            ("\n\nadd #1, #2, #3", 3, "Can't write to an immediate operand: #3"),
+            ("x: hlt\nx: hlt", 2, "Duplicate label: x"),
+            ("nop", 1, "Unknown mnemonic: nop"),
+            ("out #1, #2", 1, "out takes 1 operands, got 2"),
+            ("out 5", 1, "Invalid operand (expected `[addr]`, `#value`, or `rb+offset`): 5"),
+            ("hlt\n0: hlt", 2, "Address prefix 0 doesn't match actual address 1"),
+        ] {
+            let message = message.to_string();
+            assert_eq!(assemble(source), Err(AsmError { line, message }));
+        }
+    }
+}
@@ -0,0 +1,215 @@
+//! Running computers as futures, so that lots of them can share one thread.
+//!
+//! Nothing here depends on a particular async runtime. `LocalExecutor` and `async_channel`
+//! are just enough to run a network of computers without one.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{self, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use crate::intcode_computer::{ErrorKind, Event, IntcodeComputer, IntcodeError, Tracer};
+
+impl<I, O, T: Tracer> IntcodeComputer<I, O, T> {
+    /// Run until halt, awaiting `input()` whenever the program needs input, and `output(x)`
+    /// whenever it outputs `x`.
+    ///
+    /// Like `resume`, but the I/O handlers return futures, and the I/O handlers passed to `io`
+    /// are ignored. The instructions in between are executed synchronously.
+    pub async fn run_async<In, InFut, Out, OutFut>(&mut self, mut input: In, mut output: Out) -> Result<(), IntcodeError>
+    where
+        In: FnMut() -> InFut,
+        InFut: Future<Output=Option<i64>>,
+        Out: FnMut(i64) -> OutFut,
+        OutFut: Future<Output=()>,
+    {
+        loop {
+            match self.run_until_event()? {
+                Event::NeedsInput => match input().await {
+                    Some(x) => self.provide_input(x),
+                    None => {
+                        let kind = ErrorKind::InputExhausted;
+                        return Err(IntcodeError { kind, ip: self.ip(), rb: self.rb() });
+                    }
+                }
+                Event::Output(x) => output(x).await,
+                Event::Halted => return Ok(()),
+            }
+        }
+    }
+}
+
+/// A minimal single-threaded executor.
+#[derive(Default)]
+pub struct LocalExecutor<'a> {
+    /// Finished tasks are replaced with None, so that the others keep their ids.
+    tasks: Vec<Option<Pin<Box<dyn Future<Output=()> + 'a>>>>,
+    /// Ids of tasks that have been woken, and need polling.
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl<'a> LocalExecutor<'a> {
+    pub fn spawn(&mut self, task: impl Future<Output=()> + 'a) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(Box::pin(task)));
+    }
+
+    /// Run tasks until they've all finished, or none of them can make progress.
+    ///
+    /// Returns the number of tasks that haven't finished (i.e. are stuck).
+    pub fn run(&mut self) -> usize {
+        loop {
+            let Some(id) = self.ready.lock().unwrap().pop_front() else {
+                break;
+            };
+
+            let Some(task) = &mut self.tasks[id] else {
+                // Woken after it had already finished.
+                continue;
+            };
+
+            let waker = Waker::from(Arc::new(TaskWaker { id, ready: self.ready.clone() }));
+
+            if task.as_mut().poll(&mut Context::from_waker(&waker)).is_ready() {
+                self.tasks[id] = None;
+            }
+        }
+
+        self.tasks.iter().flatten().count()
+    }
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id);
+    }
+}
+
+/// An unbounded single-threaded channel, for connecting computers run with `run_async`.
+pub fn async_channel() -> (AsyncSender, AsyncReceiver) {
+    let shared = Rc::new(RefCell::new(Channel {
+        queue: VecDeque::new(),
+        receiver_waker: None,
+        num_senders: 1,
+    }));
+
+    (AsyncSender(shared.clone()), AsyncReceiver(shared))
+}
+
+struct Channel {
+    queue: VecDeque<i64>,
+    /// Set while the receiver is waiting.
+    receiver_waker: Option<Waker>,
+    num_senders: usize,
+}
+
+pub struct AsyncSender(Rc<RefCell<Channel>>);
+
+pub struct AsyncReceiver(Rc<RefCell<Channel>>);
+
+impl AsyncSender {
+    /// Never blocks, since the channel is unbounded.
+    pub async fn send(&self, x: i64) {
+        let mut channel = self.0.borrow_mut();
+
+        channel.queue.push_back(x);
+        if let Some(waker) = channel.receiver_waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Clone for AsyncSender {
+    fn clone(&self) -> Self {
+        self.0.borrow_mut().num_senders += 1;
+        Self(self.0.clone())
+    }
+}
+
+impl Drop for AsyncSender {
+    fn drop(&mut self) {
+        let mut channel = self.0.borrow_mut();
+
+        channel.num_senders -= 1;
+        if channel.num_senders == 0 {
+            if let Some(waker) = channel.receiver_waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+impl AsyncReceiver {
+    /// Wait for the next value. Returns None once the channel is empty and every sender has gone.
+    pub async fn recv(&self) -> Option<i64> {
+        future::poll_fn(|cx| {
+            let mut channel = self.0.borrow_mut();
+
+            match channel.queue.pop_front() {
+                Some(x) => Poll::Ready(Some(x)),
+                None if channel.num_senders == 0 => Poll::Ready(None),
+                None => {
+                    channel.receiver_waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Output one more than the input.
+    const INCREMENT: [i64; 10] = [3, 9, 1001, 9, 1, 9, 4, 9, 99, 0];
+
+    #[test]
+    fn test_ring() {
+        const NUM_MACHINES: usize = 60;
+
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..=NUM_MACHINES).map(|_| async_channel()).unzip();
+        let mut executor = LocalExecutor::default();
+
+        // Machine i reads from channel i, and writes to channel i + 1.
+        for (rx, tx) in receivers.iter().zip(senders.iter().skip(1)) {
+            executor.spawn(async move {
+                let mut computer = IntcodeComputer::new(INCREMENT.to_vec());
+                computer.run_async(|| rx.recv(), |x| tx.send(x)).await.unwrap();
+            });
+        }
+
+        let (first, last) = (&senders[0], &receivers[NUM_MACHINES]);
+        let result = Rc::new(RefCell::new(None));
+
+        let result_ref = result.clone();
+        executor.spawn(async move {
+            first.send(0).await;
+            *result_ref.borrow_mut() = last.recv().await;
+        });
+
+        assert_eq!(executor.run(), 0);
+        assert_eq!(*result.borrow(), Some(NUM_MACHINES as i64));
+    }
+
+    #[test]
+    fn test_stuck() {
+        let (tx, rx) = async_channel();
+        let mut executor = LocalExecutor::default();
+
+        executor.spawn(async {
+            let mut computer = IntcodeComputer::new(INCREMENT.to_vec());
+            computer.run_async(|| rx.recv(), |x| tx.send(x)).await.unwrap();
+        });
+
+        // Nobody ever sends anything.
+        assert_eq!(executor.run(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Counts the instructions executed by the computers that some piece of code creates, e.g. to
+//! benchmark a solution without changing how it builds its computers.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering::Relaxed;
+
+thread_local! {
+    /// The count that computers created on this thread contribute to, if any.
+    static CURRENT: RefCell<Option<Arc<AtomicU64>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, and count the instructions executed by every computer it creates on this thread
+/// (including ones it then sends to other threads, and clones of them).
+///
+/// Each computer adds to the count when it's dropped, so ones that outlive `f` aren't counted.
+pub fn count_instructions<R>(f: impl FnOnce() -> R) -> (R, u64) {
+    let count = Arc::new(AtomicU64::new(0));
+
+    let result = {
+        let _scope = Scope(CURRENT.with(|current| current.replace(Some(count.clone()))));
+        f()
+    };
+
+    (result, count.load(Relaxed))
+}
+
+/// Restores the enclosing count (if any) on drop, even if `f` panics.
+struct Scope(Option<Arc<AtomicU64>>);
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+    }
+}
+
+/// One computer's contribution to a count.
+pub(super) struct Counter {
+    count: Option<Arc<AtomicU64>>,
+    /// Executed so far, and not yet added to `count`. Kept separately so that executing an
+    /// instruction doesn't have to touch anything shared.
+    executed: u64,
+}
+
+impl Counter {
+    /// Contributes to the current thread's count, if there is one.
+    pub fn new() -> Self {
+        let count = CURRENT.with(|current| current.borrow().clone());
+        Self { count, executed: 0 }
+    }
+
+    #[inline]
+    pub fn incr(&mut self) {
+        self.executed += 1;
+    }
+}
+
+/// The clone contributes to the same count, starting from nothing.
+impl Clone for Counter {
+    fn clone(&self) -> Self {
+        Self { count: self.count.clone(), executed: 0 }
+    }
+}
+
+impl Drop for Counter {
+    fn drop(&mut self) {
+        if let Some(count) = &self.count {
+            count.fetch_add(self.executed, Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+    use crate::intcode_computer::{Event, IntcodeComputer};
+
+    #[test]
+    fn test_counting() {
+        // Output 1, then wait for input.
+        let prog = vec![104, 1, 3, 0, 99];
+
+        let mut outside = IntcodeComputer::new(prog.clone());
+
+        let ((), count) = count_instructions(|| {
+            outside.run_until_event().unwrap();
+
+            let mut computer = IntcodeComputer::new(prog.clone());
+            assert_eq!(computer.run_until_event(), Ok(Event::Output(1)));
+            assert_eq!(computer.run_until_event(), Ok(Event::NeedsInput));
+            computer.provide_input(7);
+
+            // Finish on another thread.
+            let mut clone = computer.clone();
+            thread::spawn(move || clone.run_until_event().unwrap()).join().unwrap();
+
+            computer.step().unwrap();
+            computer.step().unwrap();
+        });
+
+        // One output, plus one input on each of `computer` and its clone. Nothing from `outside`,
+        // the input instructions that were waiting, or the halts.
+        assert_eq!(count, 3);
+
+        outside.run_until_event().unwrap();
+        assert_eq!(count_instructions(|| ()).1, 0);
+    }
+}
@@ -0,0 +1,399 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use itertools::Itertools;
+use crate::intcode_computer::{Event, Instruction, IntcodeComputer, Operation, Snapshot};
+
+const HELP: &str = "\
+Commands:
+  s, step [n]          Execute n instructions (default 1).
+  c, continue          Run until a breakpoint, input request, halt, or error.
+  b, break <addr|op>   Break at an address, or before any instruction with the given mnemonic.
+  d, delete <n>        Delete breakpoint number n.
+  bl, breakpoints      List breakpoints.
+  r, regs              Show the instruction pointer and relative base.
+  l, list [addr] [n]   Disassemble n instructions (default 10) starting at addr (default ip).
+  x <addr> [len]       Show len words of memory (default 1) starting at addr.
+  set <addr> <vals...> Overwrite consecutive words of memory, starting at addr.
+  in <values...>       Queue up input values.
+  ascii [text]         Queue up a line of text (plus a newline) as ASCII input.
+  mode <num|ascii>     Show output values as numbers (default), or as ASCII text.
+  save <file>          Save the machine's state to a file.
+  load <file>          Restore the machine's state from a file.
+  h, help              Show this message.
+  q, quit              Exit the debugger.";
+
+/// The most instructions `list` or words `x` will show at once, so that a typo doesn't try to
+/// show all of memory.
+const MAX_LEN: usize = 1000;
+
+/// An interactive debugger for Intcode programs.
+///
+/// Reads commands from `input`, and writes everything (prompts, program output, etc) to `output`.
+pub fn debug(computer: IntcodeComputer, input: impl BufRead, output: impl Write) -> io::Result<()> {
+    Debugger {
+        computer,
+        breakpoints: vec![],
+        ascii_mode: false,
+        output,
+    }.repl(input)
+}
+
+struct Debugger<W> {
+    computer: IntcodeComputer,
+    breakpoints: Vec<Breakpoint>,
+    /// Show program output as text.
+    ascii_mode: bool,
+    output: W,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Breakpoint {
+    Addr(i64),
+    Op(Operation),
+}
+
+impl<W: Write> Debugger<W> {
+    fn repl(&mut self, input: impl BufRead) -> io::Result<()> {
+        writeln!(self.output, "Type `help` for a list of commands.")?;
+        self.show_location()?;
+
+        let mut lines = input.lines();
+
+        loop {
+            write!(self.output, "(icdb) ")?;
+            self.output.flush()?;
+
+            let line = match lines.next() {
+                Some(line) => line?,
+                None => break,
+            };
+
+            let words: Vec<_> = line.split_whitespace().collect();
+            let Some((&cmd, args)) = words.split_first() else {
+                continue;
+            };
+
+            if cmd == "q" || cmd == "quit" {
+                break;
+            }
+
+            if let Err(msg) = self.command(cmd, args, &line) {
+                writeln!(self.output, "Error: {}", msg)?;
+            }
+        }
+
+        writeln!(self.output)
+    }
+
+    /// Run a single command. `line` is the full command line, for commands that take raw text.
+    ///
+    /// Returns an error message if the command was invalid.
+    fn command(&mut self, cmd: &str, args: &[&str], line: &str) -> Result<(), String> {
+        match (cmd, args) {
+            ("s" | "step", []) => self.run(Some(1)),
+            ("s" | "step", [n]) => self.run(Some(parse(n)?)),
+            ("c" | "continue", []) => self.run(None),
+
+            ("b" | "break", [target]) => {
+                let bp = match target.parse() {
+                    Ok(addr) => Breakpoint::Addr(addr),
+                    Err(_) => match Operation::from_mnemonic(target) {
+                        Some(op) => Breakpoint::Op(op),
+                        None => return Err(format!("Not an address or mnemonic: {}", target)),
+                    }
+                };
+
+                self.breakpoints.push(bp);
+                self.print(format_args!("Breakpoint {}: {}", self.breakpoints.len() - 1, bp.describe()))
+            }
+            ("d" | "delete", [n]) => {
+                let n: usize = parse(n)?;
+                if n >= self.breakpoints.len() {
+                    return Err(format!("No breakpoint number {}", n));
+                }
+
+                // Note that this renumbers the later breakpoints.
+                self.breakpoints.remove(n);
+                Ok(())
+            }
+            ("bl" | "breakpoints", []) => {
+                for (i, bp) in self.breakpoints.iter().enumerate() {
+                    writeln!(self.output, "{}: {}", i, bp.describe()).map_err(io_error)?;
+                }
+                Ok(())
+            }
+
+            ("r" | "regs", []) => {
+                self.print(format_args!("ip={} rb={}", self.computer.ip(), self.computer.rb()))
+            }
+            ("l" | "list", _) if args.len() <= 2 => {
+                let addr = args.first().map_or(Ok(self.computer.ip()), parse)?;
+                let n = args.get(1).map_or(Ok(10), parse_len)?;
+                self.list(addr, n)
+            }
+            ("x", [addr]) => self.examine(parse(addr)?, 1),
+            ("x", [addr, len]) => self.examine(parse(addr)?, parse_len(len)?),
+            ("set", [addr, values @ ..]) if !values.is_empty() => {
+                let addr: i64 = parse(addr)?;
+                let values: Vec<i64> = values.iter().map(parse).try_collect()?;
+
+                for (i, x) in values.into_iter().enumerate() {
+                    self.computer.poke(addr + i as i64, x).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+
+            ("in", [_, ..]) => {
+                let values: Vec<i64> = args.iter().map(parse).try_collect()?;
+                for x in values {
+                    self.computer.provide_input(x);
+                }
+                Ok(())
+            }
+            ("ascii", _) => {
+                let text = line.trim_start().strip_prefix(cmd).unwrap();
+                // Drop the separating space, but keep any other whitespace as-is.
+                let text = text.strip_prefix(' ').unwrap_or(text);
+
+                for c in text.chars().chain(['\n']) {
+                    self.computer.provide_input(c as i64);
+                }
+                Ok(())
+            }
+            ("mode", ["num"]) => {
+                self.ascii_mode = false;
+                Ok(())
+            }
+            ("mode", ["ascii"]) => {
+                self.ascii_mode = true;
+                Ok(())
+            }
+
+            ("save", [path]) => {
+                let file = File::create(path).map_err(io_error)?;
+                self.computer.snapshot().save(file).map_err(io_error)?;
+                self.print(format_args!("Saved to {}", path))
+            }
+            ("load", [path]) => {
+                let file = File::open(path).map_err(io_error)?;
+                let snapshot = Snapshot::load(BufReader::new(file)).map_err(io_error)?;
+                self.computer.restore(&snapshot);
+                self.show_location().map_err(io_error)
+            }
+
+            ("h" | "help", []) => self.print(format_args!("{}", HELP)),
+            _ => Err(format!("Invalid command: `{}`. Type `help` for a list of commands.", line.trim())),
+        }
+    }
+
+    /// Execute up to `limit` instructions, stopping early at breakpoints or whenever the
+    /// computer can't make progress.
+    fn run(&mut self, limit: Option<usize>) -> Result<(), String> {
+        let mut num_steps = 0;
+
+        loop {
+            if limit == Some(num_steps) {
+                break;
+            }
+
+            // Don't stop at a breakpoint we're already sitting on, so that `continue` makes progress.
+            if num_steps != 0 {
+                if let Some(i) = self.breakpoint_hit() {
+                    self.print(format_args!("Hit breakpoint {}", i))?;
+                    break;
+                }
+            }
+
+            match self.computer.step() {
+                Ok(None) => {}
+                Ok(Some(Event::Output(x))) => self.show_output(x)?,
+                Ok(Some(Event::NeedsInput)) => {
+                    self.print(format_args!("Waiting for input. (See `in` and `ascii`.)"))?;
+                    break;
+                }
+                Ok(Some(Event::Halted)) => {
+                    self.print(format_args!("Halted."))?;
+                    break;
+                }
+                Err(e) => {
+                    self.print(format_args!("Error: {}", e))?;
+                    break;
+                }
+            }
+
+            num_steps += 1;
+        }
+
+        if self.ascii_mode {
+            // Make sure the location appears on its own line.
+            self.print(format_args!(""))?;
+        }
+
+        self.show_location().map_err(io_error)
+    }
+
+    /// Which breakpoint (if any) applies to the current instruction.
+    fn breakpoint_hit(&self) -> Option<usize> {
+        let ip = self.computer.ip();
+        let op = self.computer.current_instruction().map(|instr| instr.op);
+
+        self.breakpoints.iter().position(|&bp| match bp {
+            Breakpoint::Addr(addr) => addr == ip,
+            Breakpoint::Op(bp_op) => op == Some(bp_op),
+        })
+    }
+
+    fn show_output(&mut self, x: i64) -> Result<(), String> {
+        if self.ascii_mode && 0 <= x && x < 128 {
+            write!(self.output, "{}", x as u8 as char).map_err(io_error)
+        } else {
+            self.print(format_args!("Output: {}", x))
+        }
+    }
+
+    fn show_location(&mut self) -> io::Result<()> {
+        let ip = self.computer.ip();
+        let instr = self.describe_instruction(self.computer.current_instruction(), ip);
+
+        writeln!(self.output, "=> {:>5}: {}", ip, instr)
+    }
+
+    fn list(&mut self, mut addr: i64, n: usize) -> Result<(), String> {
+        for _ in 0..n {
+            let words: Vec<_> = (addr..addr + 4).map(|a| self.computer.peek(a)).try_collect().map_err(|e| e.to_string())?;
+            let instr = Instruction::decode(&words);
+
+            let marker = if addr == self.computer.ip() { "=>" } else { "  " };
+            let len = instr.as_ref().map_or(1, Instruction::num_words);
+            let text = self.describe_instruction(instr, addr);
+
+            writeln!(self.output, "{} {:>5}: {}", marker, addr, text).map_err(io_error)?;
+            addr += len as i64;
+        }
+
+        Ok(())
+    }
+
+    fn describe_instruction(&self, instr: Option<Instruction>, addr: i64) -> String {
+        match instr {
+            Some(instr) => instr.to_string(),
+            None => format!("data {}", self.computer.peek(addr).unwrap_or(0)),
+        }
+    }
+
+    fn examine(&mut self, addr: i64, len: usize) -> Result<(), String> {
+        let end = addr.checked_add(len as i64).ok_or("Address out of range")?;
+        let words: Vec<_> = (addr..end).map(|a| self.computer.peek(a)).try_collect().map_err(|e| e.to_string())?;
+
+        // Eight words to a line.
+        for (i, chunk) in words.chunks(8).enumerate() {
+            let start = addr + 8 * i as i64;
+            self.print(format_args!("{:>5}: {}", start, chunk.iter().join(" ")))?;
+        }
+
+        Ok(())
+    }
+
+    fn print(&mut self, args: std::fmt::Arguments) -> Result<(), String> {
+        writeln!(self.output, "{}", args).map_err(io_error)
+    }
+}
+
+impl Breakpoint {
+    fn describe(self) -> String {
+        match self {
+            Breakpoint::Addr(addr) => format!("address {}", addr),
+            Breakpoint::Op(op) => format!("any `{}` instruction", op.mnemonic()),
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(s: &&str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("Not a valid number: {}", s))
+}
+
+/// A number of instructions or words to show, up to `MAX_LEN`.
+fn parse_len(s: &&str) -> Result<usize, String> {
+    match parse(s)? {
+        len if len <= MAX_LEN => Ok(len),
+        len => Err(format!("Can only show up to {} at a time, not {}", MAX_LEN, len)),
+    }
+}
+
+fn io_error(e: io::Error) -> String {
+    format!("I/O error: {}", e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode_computer::assemble;
+
+    #[test]
+    fn test_debug() {
+        let prog = assemble("
+            loop:   in [x]
+                    out [x]
+                    jt [x], #loop
+                    hlt
+            x:      data 0
+        ").unwrap();
+
+        let commands = "\
+b out
+c
+in 5 0
+c
+c
+r
+set 8 7
+set 9 1 2 3
+x 8 4
+x 0 99999999999
+s 2
+l 6 2
+bogus
+";
+        let mut output = vec![];
+        debug(IntcodeComputer::new(prog), commands.as_bytes(), &mut output).unwrap();
+
+        let expected = "\
+Type `help` for a list of commands.
+=>     0: in [8]
+(icdb) Breakpoint 0: any `out` instruction
+(icdb) Waiting for input. (See `in` and `ascii`.)
+=>     0: in [8]
+(icdb) (icdb) Hit breakpoint 0
+=>     2: out [8]
+(icdb) Output: 5
+Hit breakpoint 0
+=>     2: out [8]
+(icdb) ip=2 rb=0
+(icdb) (icdb) (icdb)     8: 7 1 2 3
+(icdb) Error: Can only show up to 1000 at a time, not 99999999999
+(icdb) Output: 7
+=>     0: in [8]
+(icdb)        6: data 0
+       7: hlt
+(icdb) Error: Invalid command: `bogus`. Type `help` for a list of commands.
+(icdb) \n";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unused_mode_digits() {
+        // The computer runs 1199 as a plain halt, so the debugger should show it as one.
+        let mut output = vec![];
+        debug(IntcodeComputer::new(vec![104, 5, 1199]), "b hlt\nc\n".as_bytes(), &mut output).unwrap();
+
+        let expected = "\
+Type `help` for a list of commands.
+=>     0: out #5
+(icdb) Breakpoint 0: any `hlt` instruction
+(icdb) Output: 5
+Hit breakpoint 0
+=>     2: hlt
+(icdb) \n";
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+}
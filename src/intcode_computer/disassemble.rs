@@ -0,0 +1,141 @@
+use std::fmt;
+use std::fmt::Write as _;
+use crate::intcode_computer::instructions::{Opcode, Operation, ParameterMode};
+use ParameterMode::{Position, Immediate, Relative};
+
+/// A decoded instruction, with its parameters as they appear in memory (i.e. not yet resolved).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Instruction {
+    pub op: Operation,
+    pub params: Vec<(ParameterMode, i64)>,
+}
+
+impl Instruction {
+    /// Decode the instruction at the start of `words`.
+    ///
+    /// Returns None if the opcode or its parameter modes are invalid, or if the parameters
+    /// run off the end of `words`.
+    ///
+    /// Like the computer itself, this ignores any digits that don't mean anything (e.g. a mode
+    /// for a parameter the operation doesn't take), so `opcode` might not give back `words[0]`.
+    pub fn decode(words: &[i64]) -> Option<Self> {
+        let (&opcode, rest) = words.split_first()?;
+        let Opcode { op, modes } = Opcode::decode(opcode).ok()?;
+
+        let num_params = op.param_types().len();
+        if rest.len() < num_params {
+            return None;
+        }
+
+        let params = modes.into_iter().zip(rest.iter().copied()).take(num_params).collect();
+
+        Some(Self { op, params })
+    }
+
+    /// The opcode that encodes this operation and its parameter modes.
+    pub fn opcode(&self) -> i64 {
+        let mut opcode = self.op.code();
+        let mut place_value = 100;
+
+        for &(mode, _) in &self.params {
+            opcode += mode.digit() * place_value;
+            place_value *= 10;
+        }
+
+        opcode
+    }
+
+    /// How many words of memory this instruction occupies, including the opcode.
+    pub fn num_words(&self) -> usize {
+        1 + self.params.len()
+    }
+}
+
+/// E.g. `add [100], #3, rb-5`.
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.op.mnemonic())?;
+
+        for (i, &(mode, value)) in self.params.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+
+            match mode {
+                Position => write!(f, "{}[{}]", sep, value)?,
+                Immediate => write!(f, "{}#{}", sep, value)?,
+                Relative => write!(f, "{}rb{:+}", sep, value)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Render a program as an assembly listing, one instruction per line, prefixed by its address.
+///
+/// Words that don't decode as an instruction are listed one-at-a-time as `data`. So are opcodes
+/// with meaningless digits, which `assemble` couldn't reproduce from the instruction alone.
+pub fn disassemble(program: &[i64]) -> String {
+    let mut listing = String::new();
+    let mut addr = 0;
+
+    while addr < program.len() {
+        let instr = Instruction::decode(&program[addr..]).filter(|instr| instr.opcode() == program[addr]);
+
+        match instr {
+            Some(instr) => {
+                writeln!(listing, "{:>5}: {}", addr, instr).unwrap();
+                addr += instr.num_words();
+            }
+            None => {
+                writeln!(listing, "{:>5}: data {}", addr, program[addr]).unwrap();
+                addr += 1;
+            }
+        }
+    }
+
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        // The computer would run this as `hlt`, so we decode it as that too.
+        let instr = Instruction::decode(&[1199]).unwrap();
+        assert_eq!(instr.op, Operation::Halt);
+        assert_eq!(instr.opcode(), 99);
+
+        assert_eq!(Instruction::decode(&[2109, 5]).unwrap().to_string(), "arb #5");
+        assert_eq!(Instruction::decode(&[42]), None);
+        assert_eq!(Instruction::decode(&[1, 2, 3]), None);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let prog = [1101, 100, -1, 4, 0, 204, -3, 1005, 9, 0, 99, 42, 1, 2];
+
+        let expected = "    \
+    0: add #100, #-1, [4]
+    4: data 0
+    5: out rb-3
+    7: jt [9], #0
+   10: hlt
+   11: data 42
+   12: data 1
+   13: data 2
+";
+        assert_eq!(disassemble(&prog), expected);
+
+        // Unused mode digits, which a plain `hlt` or `arb` wouldn't preserve.
+        let prog = [699, 2109, 5, 99999];
+        let expected = "    \
+    0: data 699
+    1: data 2109
+    2: data 5
+    3: data 99999
+";
+        assert_eq!(disassemble(&prog), expected);
+    }
+}
@@ -0,0 +1,63 @@
+use std::{error, fmt};
+use ErrorKind::{InvalidOpcode, InvalidParameterMode, WriteInImmediateMode, NegativeAddress, MemoryLimitExceeded, InputExhausted, MissingIoHandler, BudgetExhausted, DeadlineExceeded};
+
+/// A fault that stopped an Intcode program.
+///
+/// The computer is left pointing at the faulting instruction, so it can be inspected afterwards.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IntcodeError {
+    pub kind: ErrorKind,
+    /// Instruction pointer (i.e. address) of the faulting instruction.
+    pub ip: i64,
+    /// Relative base at the time of the fault.
+    pub rb: i64,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ErrorKind {
+    InvalidOpcode(i64),
+    /// The full opcode, and the offending mode digit.
+    InvalidParameterMode { opcode: i64, digit: i64 },
+    /// A write parameter was given in immediate mode.
+    WriteInImmediateMode(i64),
+    /// An attempt to read or write memory at a negative address.
+    NegativeAddress(i64),
+    /// A write to this address would have taken the computer over its memory limit.
+    MemoryLimitExceeded(i64),
+    /// The input handler had nothing left to give.
+    InputExhausted,
+    /// The program did input or output, but no handler was set.
+    MissingIoHandler,
+    /// The instruction budget ran out. See `IntcodeComputer::set_budget`.
+    BudgetExhausted,
+    /// The deadline passed. See `IntcodeComputer::set_deadline`.
+    DeadlineExceeded,
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (ip={} rb={})", self.kind, self.ip, self.rb)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvalidOpcode(opcode) => write!(f, "Invalid opcode: {}", opcode),
+            InvalidParameterMode { opcode, digit } => {
+                write!(f, "Invalid parameter mode digit {} in opcode {}", digit, opcode)
+            }
+            WriteInImmediateMode(opcode) => {
+                write!(f, "Write params can't be in immediate mode: {}", opcode)
+            }
+            NegativeAddress(addr) => write!(f, "Negative address: {}", addr),
+            MemoryLimitExceeded(addr) => write!(f, "Memory limit exceeded writing to address {}", addr),
+            InputExhausted => write!(f, "Input exhausted"),
+            MissingIoHandler => write!(f, "No I/O handler set"),
+            BudgetExhausted => write!(f, "Instruction budget exhausted"),
+            DeadlineExceeded => write!(f, "Deadline exceeded"),
+        }
+    }
+}
+
+impl error::Error for IntcodeError {}
@@ -1,9 +1,10 @@
 use std::iter;
+use crate::intcode_computer::error::ErrorKind;
 use Operation::{Add, Mul, Input, Output, JumpIfTrue, JumpIfFalse, LessThan, Equals, AdjustRelBase, Halt};
 use ParameterType::{Read, Write};
 use ParameterMode::{Position, Immediate, Relative};
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Operation {
     Add,
     Mul,
@@ -22,10 +23,12 @@ pub enum Operation {
 }
 
 impl Operation {
-    pub fn new(opcode: i64) -> Self {
-        assert!(opcode >= 0);
+    pub fn new(opcode: i64) -> Result<Self, ErrorKind> {
+        if opcode < 0 {
+            return Err(ErrorKind::InvalidOpcode(opcode));
+        }
 
-        match opcode % 100 {
+        let op = match opcode % 100 {
             1 => Add,
             2 => Mul,
             3 => Input,
@@ -36,31 +39,102 @@ impl Operation {
             8 => Equals,
             9 => AdjustRelBase,
             99 => Halt,
-            _ => panic!("Invalid opcode: {}", opcode),
+            _ => return Err(ErrorKind::InvalidOpcode(opcode)),
+        };
+
+        Ok(op)
+    }
+
+    /// The last two digits of the opcode, i.e. without any parameter modes.
+    pub fn code(self) -> i64 {
+        match self {
+            Add => 1,
+            Mul => 2,
+            Input => 3,
+            Output => 4,
+            JumpIfTrue => 5,
+            JumpIfFalse => 6,
+            LessThan => 7,
+            Equals => 8,
+            AdjustRelBase => 9,
+            Halt => 99,
         }
     }
 
-    pub fn param_types(self) -> Vec<ParameterType> {
+    /// Inverse of `mnemonic`.
+    pub fn from_mnemonic(s: &str) -> Option<Self> {
+        let all = [Add, Mul, Input, Output, JumpIfTrue, JumpIfFalse, LessThan, Equals, AdjustRelBase, Halt];
+
+        all.into_iter().find(|op| op.mnemonic() == s)
+    }
+
+    /// Short name used in assembly listings.
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Add => "add",
+            Mul => "mul",
+            Input => "in",
+            Output => "out",
+            JumpIfTrue => "jt",
+            JumpIfFalse => "jf",
+            LessThan => "lt",
+            Equals => "eq",
+            AdjustRelBase => "arb",
+            Halt => "hlt",
+        }
+    }
+
+    pub fn param_types(self) -> &'static [ParameterType] {
         match self {
-            Add => vec![Read, Read, Write],
-            Mul => vec![Read, Read, Write],
-            Input => vec![Write],
-            Output => vec![Read],
-            JumpIfTrue => vec![Read, Read],
-            JumpIfFalse => vec![Read, Read],
-            LessThan => vec![Read, Read, Write],
-            Equals => vec![Read, Read, Write],
-            AdjustRelBase => vec![Read],
-            Halt => vec![],
+            Add => &[Read, Read, Write],
+            Mul => &[Read, Read, Write],
+            Input => &[Write],
+            Output => &[Read],
+            JumpIfTrue => &[Read, Read],
+            JumpIfFalse => &[Read, Read],
+            LessThan => &[Read, Read, Write],
+            Equals => &[Read, Read, Write],
+            AdjustRelBase => &[Read],
+            Halt => &[],
         }
     }
 }
 
+/// An opcode, split into its operation and parameter modes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Opcode {
+    pub op: Operation,
+    /// Only the first `op.param_types().len()` are meaningful. The rest are `Position`.
+    pub modes: [ParameterMode; 3],
+}
+
+impl Opcode {
+    /// Fails if the operation is unknown, or any of its parameters has an invalid mode
+    /// (including immediate mode for a write parameter).
+    pub fn decode(opcode: i64) -> Result<Self, ErrorKind> {
+        let op = Operation::new(opcode)?;
+        let mut modes = [Position; 3];
+
+        for ((slot, &type_), mode) in modes.iter_mut().zip(op.param_types()).zip(ParameterMode::parse_opcode(opcode)) {
+            let mode = mode?;
+            if type_ == Write && mode == Immediate {
+                return Err(ErrorKind::WriteInImmediateMode(opcode));
+            }
+
+            *slot = mode;
+        }
+
+        Ok(Self { op, modes })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ParameterType {
     Read,
     Write,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ParameterMode {
     Position,
     Immediate,
@@ -68,18 +142,29 @@ pub enum ParameterMode {
 }
 
 impl ParameterMode {
-    fn new(digit: i64) -> Self {
+    fn new(opcode: i64, digit: i64) -> Result<Self, ErrorKind> {
         match digit {
-            0 => Position,
-            1 => Immediate,
-            2 => Relative,
-            _ => panic!("Invalid parameter mode digit: {}", digit),
+            0 => Ok(Position),
+            1 => Ok(Immediate),
+            2 => Ok(Relative),
+            _ => Err(ErrorKind::InvalidParameterMode { opcode, digit }),
+        }
+    }
+
+    /// The digit that selects this mode in an opcode.
+    pub fn digit(self) -> i64 {
+        match self {
+            Position => 0,
+            Immediate => 1,
+            Relative => 2,
         }
     }
 
     /// Infinite iterator of parameter modes.
-    pub fn parse_opcode(opcode: i64) -> impl Iterator<Item=Self> {
-        assert!(opcode >= 0);
+    ///
+    /// The opcode should already have been validated by `Operation::new`.
+    pub fn parse_opcode(opcode: i64) -> impl Iterator<Item=Result<Self, ErrorKind>> {
+        debug_assert!(opcode >= 0);
 
         let mut digits = opcode / 100;
 
@@ -87,7 +172,7 @@ impl ParameterMode {
             let d = digits % 10;
             digits /= 10;
 
-            Self::new(d)
+            Self::new(opcode, d)
         })
     }
 }
@@ -0,0 +1,55 @@
+use std::time::Instant;
+use crate::intcode_computer::error::ErrorKind;
+
+/// How often to look at the clock, in instructions. Reading it every time would be slow.
+const CLOCK_CHECK_INTERVAL: u32 = 1024;
+
+/// Optional limits on how long a computer may run for.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Limits {
+    /// How many more instructions may be executed.
+    pub budget: Option<u64>,
+    pub deadline: Option<Instant>,
+    /// Instructions left until we next look at the clock.
+    clock_countdown: u32,
+}
+
+impl Limits {
+    pub fn is_active(&self) -> bool {
+        self.budget.is_some() || self.deadline.is_some()
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+        self.clock_countdown = 0;
+    }
+
+    /// Call before executing each instruction. Fails (without using anything up) if it can't go ahead.
+    pub fn check(&mut self) -> Result<(), ErrorKind> {
+        if self.budget == Some(0) {
+            return Err(ErrorKind::BudgetExhausted);
+        }
+
+        // Leave the countdown at 0 when we're out of time, so that we keep failing.
+        if let (Some(deadline), 0) = (self.deadline, self.clock_countdown) {
+            if Instant::now() >= deadline {
+                return Err(ErrorKind::DeadlineExceeded);
+            }
+
+            self.clock_countdown = CLOCK_CHECK_INTERVAL;
+        }
+
+        Ok(())
+    }
+
+    /// Call after each instruction that executed successfully (after a successful `check`).
+    pub fn use_one(&mut self) {
+        if self.deadline.is_some() {
+            self.clock_countdown -= 1;
+        }
+
+        if let Some(budget) = &mut self.budget {
+            *budget -= 1;
+        }
+    }
+}
@@ -1,51 +1,252 @@
-use std::{fmt, iter};
-use std::ops::{Index, IndexMut};
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::intcode_computer::error::ErrorKind;
+use crate::intcode_computer::instructions::Opcode;
 
+/// Addresses below this (or below the end of the program's last page, if that's further) are
+/// stored in a plain vector. Everything else goes in pages, allocated as they're written to.
+const DENSE_WORDS: usize = 1 << 16;
+
+const PAGE_WORDS: usize = 1 << 12;
+
+/// A computer's memory: dense near the program, paged beyond it.
+///
+/// This is the only backend. Making it pluggable would mean a trait on the interpreter's hottest
+/// path, and another type parameter on `IntcodeComputer`, for the sake of a second backend that
+/// nothing needs yet.
+///
+/// The limit only applies to writes. Copying in a whole memory with `clone_from` (and so
+/// `IntcodeComputer::restore`) takes however much the source has.
 pub struct Memory {
-    buf: Vec<i64>,
+    /// The program, plus however much of the space after it has been written to.
+    dense: Vec<i64>,
+    /// Where `dense` stops and `pages` start. Fixed when the memory is created, and always at the
+    /// start of a page, so that no page overlaps `dense`.
+    dense_limit: usize,
+    /// Decoded opcodes, by address, so that loops don't re-decode the same instructions over
+    /// and over. Same length as `dense`. Writing to an address clears its entry.
+    opcodes: Vec<Option<Opcode>>,
+    /// Far-away memory, by page number.
+    pages: BTreeMap<usize, Box<[i64]>>,
+    /// The most words we're allowed to allocate, if there's a limit.
+    limit: Option<usize>,
 }
 
 impl Memory {
     pub fn new(program: Vec<i64>) -> Self {
-        Self { buf: program }
+        Self {
+            dense_limit: program.len().max(DENSE_WORDS).next_multiple_of(PAGE_WORDS),
+            opcodes: vec![None; program.len()],
+            dense: program,
+            pages: BTreeMap::new(),
+            limit: None,
+        }
     }
 
-    /// Extend the buffer with 0s so that addr is in range.
-    fn extend(&mut self, addr: usize) {
-        let n = self.buf.len();
+    /// Fail writes that would need more than `limit` words in total.
+    ///
+    /// Doesn't affect anything already allocated.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.limit = limit;
+    }
 
-        if addr >= n {
-            let extra_amount = addr - n + 1;
-            self.buf.extend(iter::repeat(0).take(extra_amount));
-        }
+    /// Everything from address 0 up to the highest address that's been written to (or further),
+    /// not counting pages.
+    pub fn dense_words(&self) -> &[i64] {
+        &self.dense
     }
-}
 
-impl Index<i64> for Memory {
-    type Output = i64;
+    /// The start address and contents of each page, in order.
+    pub fn pages(&self) -> impl Iterator<Item=(i64, &[i64])> {
+        self.pages.iter().map(|(&page, words)| ((page * PAGE_WORDS) as i64, &words[..]))
+    }
 
-    fn index(&self, addr: i64) -> &i64 {
-        let addr: usize = addr.try_into().unwrap();
+    /// Unwritten addresses read as 0.
+    #[inline]
+    pub fn get(&self, addr: i64) -> Result<i64, ErrorKind> {
+        let index = Self::to_index(addr)?;
 
-        if addr < self.buf.len() {
-            &self.buf[addr]
+        if index < self.dense_limit {
+            Ok(self.dense.get(index).copied().unwrap_or(0))
         } else {
-            &0
+            Ok(self.get_far(index))
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, addr: i64, value: i64) -> Result<(), ErrorKind> {
+        let index = Self::to_index(addr)?;
+
+        if index < self.dense_limit {
+            if index >= self.dense.len() {
+                self.reserve(addr, index + 1 - self.dense.len())?;
+                self.grow_dense(index + 1);
+            }
+
+            self.dense[index] = value;
+            self.opcodes[index] = None;
+            return Ok(());
+        }
+
+        self.set_far(addr, index, value)
+    }
+
+    /// Decode the opcode at `addr`, using the cached result if we've already decoded it.
+    ///
+    /// Only opcodes in the dense part of memory are cached.
+    #[inline]
+    pub fn opcode(&mut self, addr: i64) -> Result<Opcode, ErrorKind> {
+        let index = Self::to_index(addr)?;
+
+        if index >= self.dense.len() {
+            return Opcode::decode(self.get(addr)?);
+        }
+
+        if let Some(opcode) = self.opcodes[index] {
+            return Ok(opcode);
+        }
+
+        let opcode = Opcode::decode(self.dense[index])?;
+        self.opcodes[index] = Some(opcode);
+        Ok(opcode)
+    }
+
+    /// Extend `dense` (and `opcodes`) with zeros up to `len`.
+    ///
+    /// Left to itself, `resize` might double the capacity, and so allocate past the limit.
+    #[inline(never)]
+    fn grow_dense(&mut self, len: usize) {
+        if let Some(limit) = self.limit {
+            let max_len = limit - self.pages.len() * PAGE_WORDS;
+            if len > self.dense.capacity() {
+                let capacity = (self.dense.capacity() * 2).clamp(len, max_len.max(len));
+                self.dense.reserve_exact(capacity - self.dense.len());
+                self.opcodes.reserve_exact(capacity - self.opcodes.len());
+            }
+        }
+
+        self.dense.resize(len, 0);
+        self.opcodes.resize(len, None);
+    }
+
+    // Keep the paging out of line, so as not to slow down the common case.
+    #[inline(never)]
+    fn get_far(&self, index: usize) -> i64 {
+        self.pages.get(&(index / PAGE_WORDS)).map_or(0, |page| page[index % PAGE_WORDS])
+    }
+
+    #[inline(never)]
+    fn set_far(&mut self, addr: i64, index: usize, value: i64) -> Result<(), ErrorKind> {
+        let page_num = index / PAGE_WORDS;
+        if !self.pages.contains_key(&page_num) {
+            self.reserve(addr, PAGE_WORDS)?;
+        }
+
+        let page = self.pages.entry(page_num).or_insert_with(|| vec![0; PAGE_WORDS].into_boxed_slice());
+        page[index % PAGE_WORDS] = value;
+        Ok(())
+    }
+
+    /// How many words are currently allocated.
+    pub fn num_words(&self) -> usize {
+        self.dense.len() + self.pages.len() * PAGE_WORDS
+    }
+
+    fn to_index(addr: i64) -> Result<usize, ErrorKind> {
+        addr.try_into().map_err(|_| ErrorKind::NegativeAddress(addr))
+    }
+
+    /// Check that we can allocate `words` more words, in order to write to `addr`.
+    fn reserve(&self, addr: i64, words: usize) -> Result<(), ErrorKind> {
+        match self.limit {
+            Some(limit) if self.num_words() + words > limit => Err(ErrorKind::MemoryLimitExceeded(addr)),
+            _ => Ok(()),
         }
     }
 }
 
-impl IndexMut<i64> for Memory {
-    fn index_mut(&mut self, addr: i64) -> &mut i64 {
-        let addr: usize = addr.try_into().unwrap();
+impl Clone for Memory {
+    fn clone(&self) -> Self {
+        Self {
+            dense: self.dense.clone(),
+            dense_limit: self.dense_limit,
+            opcodes: self.opcodes.clone(),
+            pages: self.pages.clone(),
+            limit: self.limit,
+        }
+    }
+
+    /// Re-use the existing allocations, if they're big enough.
+    ///
+    /// Keeps our own limit, rather than taking the source's, but doesn't check the source
+    /// against it.
+    fn clone_from(&mut self, source: &Self) {
+        self.dense.clone_from(&source.dense);
+        self.dense_limit = source.dense_limit;
+        self.opcodes.clone_from(&source.opcodes);
+        self.pages.clone_from(&source.pages);
+    }
+}
 
-        self.extend(addr);
-        &mut self.buf[addr]
+/// Same contents, ignoring the opcode cache and limit.
+///
+/// Memory that's only been extended with zeros compares unequal to memory that hasn't, even
+/// though they read the same.
+impl PartialEq for Memory {
+    fn eq(&self, other: &Self) -> bool {
+        self.dense == other.dense && self.pages == other.pages
     }
 }
 
+impl Eq for Memory {}
+
 impl fmt::Debug for Memory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.buf)
+        write!(f, "{:?}", self.dense)?;
+
+        for (addr, words) in self.pages() {
+            write!(f, " @{}: {:?}", addr, words)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_far_addresses() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        let far = 1_000_000_000_000;
+
+        memory.set(far, 42).unwrap();
+        memory.set(far + 1, 43).unwrap();
+        memory.set(10, 7).unwrap();
+
+        assert_eq!(memory.get(far), Ok(42));
+        assert_eq!(memory.get(far + 1), Ok(43));
+        assert_eq!(memory.get(far + 2), Ok(0));
+        assert_eq!(memory.get(far * 2), Ok(0));
+        assert_eq!(memory.get(10), Ok(7));
+        assert_eq!(memory.num_words(), 11 + PAGE_WORDS);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+        memory.set_limit(Some(100));
+
+        // Growing to 100 mustn't leave room for more.
+        memory.set(60, 1).unwrap();
+        memory.set(99, 1).unwrap();
+        assert_eq!(memory.set(100, 1), Err(ErrorKind::MemoryLimitExceeded(100)));
+        assert_eq!(memory.set(1 << 40, 1), Err(ErrorKind::MemoryLimitExceeded(1 << 40)));
+
+        // Writing to memory that's already allocated is fine.
+        memory.set(50, 1).unwrap();
+        assert_eq!(memory.num_words(), 100);
+        assert!(memory.dense.capacity() <= 100);
     }
 }
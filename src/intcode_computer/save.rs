@@ -0,0 +1,172 @@
+use std::io::{self, BufRead, Write};
+use itertools::Itertools;
+use crate::intcode_computer::Snapshot;
+use crate::intcode_computer::mem::Memory;
+
+const VERSION: u32 = 2;
+
+/// Save files are plain text, e.g.:
+///
+/// ```text
+/// intcode-snapshot 2
+/// ip 2
+/// rb 0
+/// input 5,6
+/// memory 3,7,4,7,1105,1,0,0
+/// page 1000000 0,0,9
+/// ```
+///
+/// with one `page` line per page of far-away memory, giving its start address and contents
+/// (minus any trailing zeros). Version 1 files are the same, but without pages.
+impl Snapshot {
+    /// Every save file starts with this.
+    pub const HEADER: &'static str = "intcode-snapshot";
+
+    pub fn save(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "{} {}", Self::HEADER, VERSION)?;
+        writeln!(w, "ip {}", self.ip)?;
+        writeln!(w, "rb {}", self.rb)?;
+        writeln!(w, "input {}", self.pending_input.iter().join(","))?;
+        writeln!(w, "memory {}", self.memory.dense_words().iter().join(","))?;
+
+        for (addr, words) in self.memory.pages() {
+            let len = words.iter().rposition(|&x| x != 0).map_or(0, |i| i + 1);
+            writeln!(w, "page {} {}", addr, words[..len].iter().join(","))?;
+        }
+
+        w.flush()
+    }
+
+    /// Errors are `io::ErrorKind::InvalidData` if the file is corrupted, or from an unknown version.
+    pub fn load(r: impl BufRead) -> io::Result<Self> {
+        let mut lines = r.lines();
+        let mut field = |name| -> io::Result<String> {
+            let line = lines.next().ok_or_else(|| invalid(format!("Missing field: {}", name)))??;
+
+            match line.split_once(' ') {
+                Some((key, value)) if key == name => Ok(value.to_string()),
+                None if line == name => Ok(String::new()),
+                _ => Err(invalid(format!("Expected field {}, got: {}", name, line))),
+            }
+        };
+
+        let version = field(Self::HEADER)?;
+        if version != "1" && version != VERSION.to_string() {
+            return Err(invalid(format!("Unsupported save file version: {}", version)));
+        }
+
+        let ip = parse_num(&field("ip")?)?;
+        let rb = parse_num(&field("rb")?)?;
+        let pending_input = parse_list(&field("input")?)?;
+        let mut memory = Memory::new(parse_list(&field("memory")?)?);
+
+        for line in lines {
+            let line = line?;
+            let page = line.strip_prefix("page ").ok_or_else(|| invalid(format!("Expected a page, got: {}", line)))?;
+
+            let (addr, words) = page.split_once(' ').unwrap_or((page, ""));
+            let addr = parse_num(addr)?;
+            let words: Vec<_> = parse_list(words)?;
+
+            for (i, x) in words.into_iter().enumerate() {
+                memory.set(addr + i as i64, x).map_err(|e| invalid(e.to_string()))?;
+            }
+        }
+
+        Ok(Self { ip, rb, memory, pending_input })
+    }
+}
+
+fn parse_list<C: FromIterator<i64>>(s: &str) -> io::Result<C> {
+    if s.is_empty() {
+        return Ok(C::from_iter([]));
+    }
+
+    s.split(',').map(parse_num).collect()
+}
+
+fn parse_num(s: &str) -> io::Result<i64> {
+    s.parse().map_err(|_| invalid(format!("Not a number: {}", s)))
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode_computer::{Event, IntcodeComputer};
+
+    #[test]
+    fn test_save_load() {
+        // Echo input forever.
+        let mut computer = IntcodeComputer::new(vec![3, 7, 4, 7, 1105, 1, 0, 0]);
+        computer.provide_input(4);
+        computer.run_until_event().unwrap();
+        computer.provide_input(5);
+        computer.provide_input(6);
+
+        let mut file = vec![];
+        computer.snapshot().save(&mut file).unwrap();
+
+        let expected = "\
+intcode-snapshot 2
+ip 4
+rb 0
+input 5,6
+memory 3,7,4,7,1105,1,0,4
+";
+        assert_eq!(String::from_utf8(file.clone()).unwrap(), expected);
+
+        let mut resumed = IntcodeComputer::from_snapshot(Snapshot::load(&file[..]).unwrap());
+        assert_eq!(resumed.run_until_event(), Ok(Event::Output(5)));
+        assert_eq!(resumed.run_until_event(), Ok(Event::Output(6)));
+        assert_eq!(resumed.run_until_event(), Ok(Event::NeedsInput));
+    }
+
+    #[test]
+    fn test_far_memory() {
+        let mut computer = IntcodeComputer::new(vec![99]);
+        computer.poke(1_000_001, 7).unwrap();
+
+        let mut file = vec![];
+        computer.snapshot().save(&mut file).unwrap();
+        assert!(String::from_utf8(file.clone()).unwrap().contains("\nmemory 99\npage 999424 0,0,0,"));
+
+        let resumed = IntcodeComputer::from_snapshot(Snapshot::load(&file[..]).unwrap());
+        assert_eq!(resumed.peek(1_000_001), Ok(7));
+        assert_eq!(resumed.peek(1_000_002), Ok(0));
+
+        // A program that doesn't end on a page boundary, written to just past its end. Loading that
+        // write mustn't clobber the end of the program.
+        let len = (1 << 16) + 5;
+        let mut computer = IntcodeComputer::new(vec![1; len]);
+        computer.poke(len as i64 + 5, 7).unwrap();
+
+        let mut file = vec![];
+        computer.snapshot().save(&mut file).unwrap();
+
+        let resumed = IntcodeComputer::from_snapshot(Snapshot::load(&file[..]).unwrap());
+        assert_eq!(resumed.peek(len as i64 - 1), Ok(1));
+        assert_eq!(resumed.peek(len as i64 + 5), Ok(7));
+
+        // Old save files don't have pages, but can still be loaded.
+        Snapshot::load("intcode-snapshot 1\nip 0\nrb 0\ninput\nmemory 99\n".as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_load_errors() {
+        for file in [
+            "",
+            "intcode-snapshot 3\nip 0\nrb 0\ninput\nmemory 99\n",
+            "intcode-snapshot 1\nip 0\nrb 0\nmemory 99\n",
+            "intcode-snapshot 1\nip 0\nrb 0\ninput\nmemory 9x9\n",
+            "intcode-snapshot 2\nip 0\nrb 0\ninput\nmemory 99\npage x 1\n",
+            "intcode-snapshot 2\nip 0\nrb 0\ninput\nmemory 99\nbogus\n",
+        ] {
+            let err = Snapshot::load(file.as_bytes()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::io::{self, Write};
+use crate::intcode_computer::Operation;
+
+/// Observes every instruction an `IntcodeComputer` executes. See `IntcodeComputer::trace`.
+pub trait Tracer {
+    /// Set this to false to skip the bookkeeping needed to build trace records.
+    const ENABLED: bool = true;
+
+    /// Called after each instruction is successfully executed.
+    ///
+    /// Not called for input instructions that are waiting on input, since they don't execute.
+    fn trace(&mut self, record: &TraceRecord);
+}
+
+/// One executed instruction.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TraceRecord<'a> {
+    /// Address of the instruction.
+    pub ip: i64,
+    /// Relative base, as of just before the instruction executed.
+    pub rb: i64,
+    pub op: Operation,
+    /// Read-parameter arguments are values, and write-parameter arguments are addresses.
+    pub args: &'a [i64],
+    /// The memory write that the instruction made, if any.
+    pub write: Option<MemoryWrite>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MemoryWrite {
+    pub addr: i64,
+    pub old: i64,
+    pub new: i64,
+}
+
+/// The default tracer, which does nothing, and costs nothing.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NoTrace;
+
+impl Tracer for NoTrace {
+    const ENABLED: bool = false;
+
+    fn trace(&mut self, _: &TraceRecord) {}
+}
+
+/// Lets you keep hold of a tracer while the computer is using it.
+impl<T: Tracer> Tracer for &mut T {
+    const ENABLED: bool = T::ENABLED;
+
+    fn trace(&mut self, record: &TraceRecord) {
+        (**self).trace(record);
+    }
+}
+
+/// Counts executed instructions.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct InstructionCounter {
+    pub count: u64,
+}
+
+impl Tracer for InstructionCounter {
+    fn trace(&mut self, _: &TraceRecord) {
+        self.count += 1;
+    }
+}
+
+/// Writes one line of text per instruction, e.g. `   12: add 3 4 100 ; [100] 0 -> 7`.
+///
+/// The output is deterministic, so two runs can be compared with ordinary tools like `diff`.
+pub struct TraceLog<W> {
+    writer: W,
+    /// The first error we ran into. After that, we stop writing.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TraceLog<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, error: None }
+    }
+
+    /// Flush the log, and report any errors that happened while writing it.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_record(&mut self, record: &TraceRecord) -> io::Result<()> {
+        let &TraceRecord { ip, rb, op, args, write } = record;
+
+        write!(self.writer, "{:>5}: {}", ip, op.mnemonic())?;
+        for arg in args {
+            write!(self.writer, " {}", arg)?;
+        }
+
+        if let Some(MemoryWrite { addr, old, new }) = write {
+            write!(self.writer, " ; [{}] {} -> {}", addr, old, new)?;
+        }
+        if op == Operation::AdjustRelBase {
+            write!(self.writer, " ; rb {} -> {}", rb, rb + args[0])?;
+        }
+
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> Tracer for TraceLog<W> {
+    fn trace(&mut self, record: &TraceRecord) {
+        if self.error.is_none() {
+            self.error = self.write_record(record).err();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode_computer::{assemble, IntcodeComputer};
+
+    #[test]
+    fn test_trace_log() {
+        let prog = assemble("
+                    in [x]
+                    arb #x
+                    mul [x], #3, rb+0
+                    out [x]
+                    hlt
+            x:      data 0
+        ").unwrap();
+
+        let mut counter = InstructionCounter::default();
+        let mut log = TraceLog::new(vec![]);
+
+        let mut computer = IntcodeComputer::new(prog.clone()).trace(&mut counter);
+        computer.provide_input(5);
+        computer.run_until_event().unwrap();
+        computer.run_until_event().unwrap();
+        assert_eq!(counter.count, 5);
+
+        let mut input = [5].into_iter();
+        IntcodeComputer::new(prog).io(|| input.next(), |_| {}).trace(&mut log).run().unwrap();
+
+        let expected = "    \
+    0: in 11 ; [11] 0 -> 5
+    2: arb 11 ; rb 0 -> 11
+    4: mul 5 3 11 ; [11] 5 -> 15
+    8: out 15
+   10: hlt
+";
+        assert_eq!(String::from_utf8(log.finish().unwrap()).unwrap(), expected);
+    }
+}
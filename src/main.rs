@@ -1,38 +1,315 @@
 use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::PathBuf;
 use std::process;
+use itertools::Itertools;
+use advent_2019::{BenchResult, Part, bench, days, default_input_path, run};
+use advent_2019::intcode_computer::{assemble, debug, disassemble, read_intcode_program, IntcodeComputer, Snapshot};
 
 fn main() {
     let mut args = env::args();
     let prog_name = args.next().unwrap();
     let args: Vec<_> = args.collect();
 
-    let usage = || {
+    let usage = || -> ! {
         eprintln!();
         eprint_usage(&prog_name);
         process::exit(1)
     };
 
-    if args.len() != 1 {
-        eprintln!("Expected 1 argument, got {}.", args.len());
-        usage();
+    let subcommand = args.first().map(String::as_str);
+
+    if subcommand == Some("disasm") || subcommand == Some("asm") {
+        if args.len() > 2 {
+            eprintln!("Expected at most 1 argument to {}, got {}.", args[0], args.len() - 1);
+            usage();
+        }
+
+        let path = args.get(1).map(String::as_str);
+
+        if subcommand == Some("disasm") {
+            let prog = read_intcode_program(BufReader::new(open_input(path)));
+            print!("{}", disassemble(&prog));
+        } else {
+            let mut source = String::new();
+            open_input(path).read_to_string(&mut source).unwrap();
+
+            match assemble(&source) {
+                Ok(prog) => println!("{}", prog.iter().join(",")),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1)
+                }
+            }
+        }
+
+        return;
     }
 
-    let day: u32 = match args[0].parse() {
-        Ok(n) => if 1 <= n && n <= 25 {
-            n
+    if subcommand == Some("debug") {
+        if args.len() != 2 {
+            eprintln!("Expected 1 argument to debug, got {}.", args.len() - 1);
+            usage();
+        }
+
+        // Stdin is for debugger commands, so the program has to come from a file.
+        // It can be a save file instead of a program, to pick up where we left off.
+        let mut file = BufReader::new(open_input(Some(&args[1])));
+        let computer = if file.fill_buf().unwrap().starts_with(Snapshot::HEADER.as_bytes()) {
+            match Snapshot::load(file) {
+                Ok(snapshot) => IntcodeComputer::from_snapshot(snapshot),
+                Err(e) => {
+                    eprintln!("Couldn't load {}: {}", args[1], e);
+                    process::exit(1)
+                }
+            }
         } else {
-            eprintln!("Not a number from 1 through 25: {}", n);
+            IntcodeComputer::new(read_intcode_program(file))
+        };
+
+        debug(computer, io::stdin().lock(), io::stdout()).unwrap();
+        return;
+    }
+
+    if subcommand == Some("all") {
+        let format = match &args[1..] {
+            [] => Format::Text,
+            [opt, format] if opt == "--format" => parse_format(format).unwrap_or_else(|| usage()),
+            _ => {
+                eprintln!("Expected at most `--format <format>` after all.");
+                usage()
+            }
+        };
+
+        if !run_all(format) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if subcommand != Some("run") && subcommand != Some("bench") {
+        match subcommand {
+            Some(cmd) => eprintln!("Unknown command: {}", cmd),
+            None => eprintln!("Expected a command."),
+        }
+        usage();
+    }
+    let benchmarking = subcommand == Some("bench");
+
+    if args.len() < 2 {
+        eprintln!("Expected a day to {}.", args[0]);
+        usage();
+    }
+
+    // None for all of them.
+    let day = match args[1].parse() {
+        Ok(n) if 1 <= n && n <= 25 => Some(n),
+        _ if benchmarking && args[1] == "all" => None,
+        _ => {
+            eprintln!("Not a number from 1 through 25: {}", &args[1]);
             usage()
         }
-        Err(_) => {
-            eprintln!("Not a u32: {}", &args[0]);
+    };
+
+    // Anything we don't recognise is for the day itself.
+    let mut parts = &Part::BOTH[..];
+    let mut input_path = None;
+    let mut runs = 5;
+    let mut format = Format::Text;
+    let mut options = vec![];
+
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        let mut value = || rest.next().cloned().unwrap_or_else(|| {
+            eprintln!("{} needs an argument.", arg);
             usage()
+        });
+
+        match arg.as_str() {
+            "--part" => match value().parse::<Part>() {
+                Ok(Part::One) => parts = &Part::BOTH[..1],
+                Ok(Part::Two) => parts = &Part::BOTH[1..],
+                Err(e) => {
+                    eprintln!("{}", e);
+                    usage()
+                }
+            }
+            "--input" => input_path = Some(PathBuf::from(value())),
+            "--format" if !benchmarking => format = parse_format(&value()).unwrap_or_else(|| usage()),
+            "--runs" if benchmarking => match value().parse() {
+                Ok(n) if n > 0 => runs = n,
+                _ => {
+                    eprintln!("--runs needs a positive number.");
+                    usage()
+                }
+            }
+            _ => options.push(arg.clone()),
+        }
+    }
+
+    let Some(day) = day else {
+        if input_path.is_some() || !options.is_empty() {
+            eprintln!("Days can't be given inputs or options when benchmarking all of them.");
+            usage();
+        }
+
+        if !bench_all(parts, runs) {
+            process::exit(1);
+        }
+        return;
+    };
+
+    let input_path = input_path.unwrap_or_else(|| default_input_path(day));
+
+    let result = if benchmarking {
+        bench(day, parts, &input_path, &options, runs).map(|results| {
+            print_bench_header();
+            for (&part, result) in parts.iter().zip(results) {
+                print_bench_result(day, part, &result);
+            }
+        })
+    } else {
+        run(day, parts, &input_path, &options).map(|results| {
+            for result in results {
+                match format {
+                    Format::Text => println!("{}", result.answer),
+                    Format::Json => println!("{}", result.to_json()),
+                }
+            }
+        })
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1)
+    }
+}
+
+/// Benchmark every solved day on its usual input, and print a table of timings.
+///
+/// Returns false if any of them couldn't be run.
+fn bench_all(parts: &[Part], runs: usize) -> bool {
+    let mut all_ok = true;
+
+    print_bench_header();
+
+    for day in days() {
+        match bench(day, parts, &default_input_path(day), &[], runs) {
+            Ok(results) => {
+                for (&part, result) in parts.iter().zip(results) {
+                    print_bench_result(day, part, &result);
+                }
+            }
+            Err(e) => {
+                println!("{:>3}  {}", day, e);
+                all_ok = false;
+            }
         }
+    }
+
+    all_ok
+}
+
+fn print_bench_header() {
+    println!("{:>3}  {:>4}  {:>10}  {:>10}  {:>10}  {:>14}", "Day", "Part", "Min", "Median", "Max", "Instructions");
+}
+
+fn print_bench_result(day: u32, part: Part, result: &BenchResult) {
+    // Days that don't use Intcode don't execute any instructions.
+    let instructions = match result.instructions {
+        0 => "-".to_string(),
+        n => n.to_string(),
     };
 
-    advent_2019::solve(day);
+    println!("{:>3}  {:>4}  {:>10.2?}  {:>10.2?}  {:>10.2?}  {:>14}",
+             day, part, result.min(), result.median(), result.max(), instructions);
+}
+
+/// Run every solved day on its usual input, and print a table of answers.
+///
+/// Returns false if any of them couldn't be run.
+fn run_all(format: Format) -> bool {
+    let mut all_ok = true;
+
+    if format == Format::Text {
+        println!("{:>3}  {:<16}  Part 2", "Day", "Part 1");
+    }
+
+    for day in days() {
+        match (run(day, &Part::BOTH, &default_input_path(day), &[]), format) {
+            (Ok(results), Format::Text) => println!("{:>3}  {:<16}  {}", day, results[0].answer, results[1].answer),
+            (Ok(results), Format::Json) => {
+                for result in results {
+                    println!("{}", result.to_json());
+                }
+            }
+            (Err(e), Format::Text) => {
+                println!("{:>3}  {}", day, e);
+                all_ok = false;
+            }
+            // Keep stdout valid JSON.
+            (Err(e), Format::Json) => {
+                eprintln!("Day {}: {}", day, e);
+                all_ok = false;
+            }
+        }
+    }
+
+    all_ok
+}
+
+/// How `run` and `all` print answers.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Text,
+    /// One `{"day": ..., "part": ..., "answer": ..., "elapsed_ms": ...}` object per line.
+    Json,
+}
+
+fn parse_format(s: &str) -> Option<Format> {
+    match s {
+        "text" => Some(Format::Text),
+        "json" => Some(Format::Json),
+        _ => {
+            eprintln!("Unknown format: {} (expected text or json)", s);
+            None
+        }
+    }
+}
+
+/// Open the given file, or stdin if there isn't one.
+fn open_input(path: Option<&str>) -> Box<dyn Read> {
+    match path {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("Couldn't open {}: {}", path, e);
+                process::exit(1)
+            }
+        }
+        None => Box::new(io::stdin()),
+    }
 }
 
 fn eprint_usage(prog_name: &str) {
-    eprintln!("Usage: `{} <num>`\nwhere <num> is a number from 1 through 25.", prog_name);
+    eprintln!("Usage: `{} run <day> [--part 1|2] [--input <file>] [--format text|json] [<options>...]`", prog_name);
+    eprintln!("where <day> is a number from 1 through 25. The input is read from inputs/<day> by default.");
+    eprintln!("Day 23 takes `--threads` (run each networked computer on its own thread),");
+    eprintln!("`--capture <file>` (record network traffic), and `--replay <file>` (re-run the NAT on a recording).");
+    eprintln!("Its NAT can be changed with `--nat-broadcast`, `--nat-dest <addr>`, `--nat-max-wakeups <k>`,");
+    eprintln!("and `--nat-stats`, and the network's shape with `--cpus <n>`, `--nat-addr <addr>`,");
+    eprintln!("and `--unknown-addrs drop|log|error`.");
+    eprintln!();
+    eprintln!("Or: `{} all [--format text|json]`\nto run every day that's been solved so far, and print a table of answers.", prog_name);
+    eprintln!("JSON output is one {{\"day\", \"part\", \"answer\", \"elapsed_ms\"}} object per line.");
+    eprintln!();
+    eprintln!("Or: `{} bench <day>|all [--part 1|2] [--runs <n>] [--input <file>] [<options>...]`", prog_name);
+    eprintln!("to time each part over several runs (5 by default), and count the Intcode instructions executed.");
+    eprintln!();
+    eprintln!("Or: `{} disasm [<file>]`\nto disassemble an Intcode program (read from stdin by default).", prog_name);
+    eprintln!();
+    eprintln!("Or: `{} asm [<file>]`\nto assemble an Intcode program (read from stdin by default).", prog_name);
+    eprintln!();
+    eprintln!("Or: `{} debug <file>`\nto step through an Intcode program (or a save file) interactively.", prog_name);
 }
@@ -0,0 +1,189 @@
+use std::{error, fmt, fs, io};
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::intcode_computer::count_instructions;
+use crate::solutions::{Answer, Part, Solution, solution};
+
+/// Something that stopped us from running a solution.
+#[derive(Debug)]
+pub enum RunError {
+    NotSolved(u32),
+    InvalidOptions { day: u32, msg: String },
+    Input { path: PathBuf, error: io::Error },
+    /// See `Solution::try_part`.
+    Failed { day: u32, part: Part, msg: String },
+}
+
+/// Where a day's puzzle input lives, unless we're told otherwise.
+pub fn default_input_path(day: u32) -> PathBuf {
+    Path::new("inputs").join(day.to_string())
+}
+
+/// One part's answer, from `run`.
+#[derive(Debug, Clone)]
+pub struct RunResult {
+    pub day: u32,
+    pub part: Part,
+    pub answer: Answer,
+    pub elapsed: Duration,
+}
+
+/// Timings from `bench`.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    /// One per run, fastest first.
+    pub times: Vec<Duration>,
+    /// Intcode instructions executed per run (on average). 0 for days that don't use Intcode.
+    pub instructions: u64,
+}
+
+/// Solve the given `parts` of `day`, with the input from `input_path`.
+///
+/// `options` are any extra command-line arguments. Only some days take them.
+pub fn run(day: u32, parts: &[Part], input_path: &Path, options: &[String]) -> Result<Vec<RunResult>, RunError> {
+    let (solution, input) = prepare(day, input_path, options)?;
+
+    parts.iter().map(|&part| {
+        let start = Instant::now();
+        let answer = solution.try_part(part, &input).map_err(|msg| RunError::Failed { day, part, msg })?;
+        Ok(RunResult { day, part, answer, elapsed: start.elapsed() })
+    }).collect()
+}
+
+/// Like `run`, but solves each part `runs` times, and times it.
+pub fn bench(day: u32, parts: &[Part], input_path: &Path, options: &[String], runs: usize) -> Result<Vec<BenchResult>, RunError> {
+    assert!(runs > 0);
+    let (solution, input) = prepare(day, input_path, options)?;
+
+    parts.iter().map(|&part| {
+        let (times, instructions) = count_instructions(|| {
+            (0..runs).map(|_| {
+                let start = Instant::now();
+                black_box(solution.try_part(part, &input)).map_err(|msg| RunError::Failed { day, part, msg })?;
+                Ok(start.elapsed())
+            }).collect::<Result<Vec<_>, _>>()
+        });
+
+        let mut times = times?;
+        times.sort();
+
+        Ok(BenchResult { times, instructions: instructions / runs as u64 })
+    }).collect()
+}
+
+fn prepare(day: u32, input_path: &Path, options: &[String]) -> Result<(Box<dyn Solution>, String), RunError> {
+    let mut solution = solution(day).ok_or(RunError::NotSolved(day))?;
+    solution.configure(options).map_err(|msg| RunError::InvalidOptions { day, msg })?;
+
+    let input = fs::read_to_string(input_path).map_err(|error| {
+        RunError::Input { path: input_path.to_path_buf(), error }
+    })?;
+
+    Ok((solution, input))
+}
+
+impl RunResult {
+    /// E.g. `{"day": 1, "part": 2, "answer": 4907345, "elapsed_ms": 0.012}`, all on one line.
+    ///
+    /// Numeric answers are JSON numbers, and any others are strings.
+    pub fn to_json(&self) -> String {
+        let answer = match &self.answer {
+            Answer::Number(n) => n.to_string(),
+            Answer::Text(s) => json_string(s),
+        };
+
+        format!(r#"{{"day": {}, "part": {}, "answer": {}, "elapsed_ms": {:.3}}}"#,
+                self.day, self.part, answer, self.elapsed.as_secs_f64() * 1000.0)
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+impl BenchResult {
+    pub fn min(&self) -> Duration {
+        self.times[0]
+    }
+
+    pub fn median(&self) -> Duration {
+        self.times[self.times.len() / 2]
+    }
+
+    pub fn max(&self) -> Duration {
+        self.times[self.times.len() - 1]
+    }
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotSolved(day) => write!(f, "Day {} isn't solved yet", day),
+            Self::InvalidOptions { day, msg } => write!(f, "Invalid options for day {}: {}", day, msg),
+            Self::Input { path, error } => write!(f, "Couldn't read {}: {}", path.display(), error),
+            Self::Failed { day, part, msg } => write!(f, "Day {} part {} failed: {}", day, part, msg),
+        }
+    }
+}
+
+impl error::Error for RunError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        let input = default_input_path(1);
+
+        assert!(matches!(run(4, &Part::BOTH, &input, &[]), Err(RunError::NotSolved(4))));
+        assert!(matches!(run(1, &Part::BOTH, &input, &["-x".to_string()]), Err(RunError::InvalidOptions { day: 1, .. })));
+
+        let missing = Path::new("no/such/file");
+        assert!(bench(1, &[Part::One], missing, &[], 1).is_err());
+        let err = run(1, &[Part::One], missing, &[]).unwrap_err();
+        assert!(err.to_string().starts_with("Couldn't read no/such/file: "));
+    }
+
+    #[test]
+    fn test_json() {
+        let mut result = RunResult {
+            day: 1,
+            part: Part::Two,
+            answer: Answer::Number(-42),
+            elapsed: Duration::from_micros(1500),
+        };
+        assert_eq!(result.to_json(), r#"{"day": 1, "part": 2, "answer": -42, "elapsed_ms": 1.500}"#);
+
+        result.answer = Answer::Text("a \"b\"\n\\c\t".to_string());
+        assert_eq!(result.to_json(), r#"{"day": 1, "part": 2, "answer": "a \"b\"\n\\c\u0009", "elapsed_ms": 1.500}"#);
+    }
+
+    #[test]
+    fn test_bench() {
+        let path = default_input_path(2);
+        let results = bench(2, &[Part::One], &path, &[], 3).unwrap();
+
+        assert_eq!(results.len(), 1);
+        let result = &results[0];
+        assert_eq!(result.times.len(), 3);
+        assert!(result.min() <= result.median() && result.median() <= result.max());
+        // Not affected by any other tests running at the same time.
+        assert_eq!(result.instructions, 36);
+    }
+}
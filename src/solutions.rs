@@ -1,5 +1,9 @@
+use std::fmt;
+use std::str::FromStr;
+
 pub mod day1;
 pub mod day2;
+pub mod day3;
 pub mod day5;
 pub mod day9;
 pub mod day17;
@@ -8,3 +12,151 @@ pub mod day21;
 pub mod day22;
 pub mod day23;
 pub mod day24;
+
+mod geometry;
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Answer {
+    Number(i128),
+    Text(String),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const BOTH: [Part; 2] = [Part::One, Part::Two];
+}
+
+/// One day's puzzle. `input` is the contents of the puzzle input file.
+pub trait Solution {
+    fn part_1(&self, input: &str) -> Answer;
+    fn part_2(&self, input: &str) -> Answer;
+
+    fn part(&self, part: Part, input: &str) -> Answer {
+        match part {
+            Part::One => self.part_1(input),
+            Part::Two => self.part_2(input),
+        }
+    }
+
+    /// Like `part`, but can fail for reasons other than bugs, e.g. options that the input can't
+    /// cope with. Most days can't fail like that.
+    fn try_part(&self, part: Part, input: &str) -> Result<Answer, String> {
+        Ok(self.part(part, input))
+    }
+
+    /// Takes any extra command-line arguments. Most days don't take any.
+    fn configure(&mut self, options: &[String]) -> Result<(), String> {
+        if options.is_empty() {
+            Ok(())
+        } else {
+            Err("This day doesn't take any options".to_string())
+        }
+    }
+}
+
+type Constructor = fn() -> Box<dyn Solution>;
+
+/// Every day solved so far, in order.
+const REGISTRY: &[(u32, Constructor)] = &[
+    (1, boxed::<day1::Day1>),
+    (2, boxed::<day2::Day2>),
+    (3, boxed::<day3::Day3>),
+    (5, boxed::<day5::Day5>),
+    (9, boxed::<day9::Day9>),
+    (17, boxed::<day17::Day17>),
+    (19, boxed::<day19::Day19>),
+    (21, boxed::<day21::Day21>),
+    (22, boxed::<day22::Day22>),
+    (23, boxed::<day23::Day23>),
+    (24, boxed::<day24::Day24>),
+];
+
+fn boxed<S: Solution + Default + 'static>() -> Box<dyn Solution> {
+    Box::new(S::default())
+}
+
+/// The days we have solutions for, in order.
+pub fn days() -> impl Iterator<Item=u32> {
+    REGISTRY.iter().map(|&(day, _)| day)
+}
+
+/// Returns None if we haven't solved that day yet.
+pub fn solution(day: u32) -> Option<Box<dyn Solution>> {
+    REGISTRY.iter().find(|&&(d, _)| d == day).map(|(_, new)| new())
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            // Respecting any width etc., for tables.
+            Self::Number(n) => fmt::Display::fmt(n, f),
+            Self::Text(s) => fmt::Display::fmt(s, f),
+        }
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::One => f.pad("1"),
+            Self::Two => f.pad("2"),
+        }
+    }
+}
+
+impl FromStr for Part {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1" => Ok(Self::One),
+            "2" => Ok(Self::Two),
+            _ => Err(format!("Expected part 1 or 2, not {:?}", s)),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Self::Number(n.try_into().expect("Answer too big"))
+                }
+            }
+        )*
+    };
+}
+
+// Only the ones we need. (Blanket impls would be nicer, but would conflict with `From<String>`.)
+answer_from_int!(i64, isize, u32, usize, u128);
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Self::Text(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        assert!(days().zip(days().skip(1)).all(|(a, b)| a < b));
+        assert!(solution(1).is_some());
+        assert!(solution(4).is_none());
+
+        let mut day1 = solution(1).unwrap();
+        assert!(day1.configure(&[]).is_ok());
+        assert!(day1.configure(&["--threads".to_string()]).is_err());
+        assert_eq!(day1.part_1("12\n1969\n"), Answer::Number(2 + 654));
+        assert_eq!(day1.part_1("12\n").to_string(), "2");
+    }
+}
@@ -1,18 +1,21 @@
-use std::io;
 use std::io::BufRead;
+use crate::solutions::{Answer, Solution};
 
 fn read_input(input: impl BufRead) -> Vec<u32> {
     input.lines().map(|line| line.unwrap().parse().unwrap()).collect()
 }
 
-pub fn main() {
-    let nums = read_input(io::stdin().lock());
+#[derive(Default)]
+pub struct Day1;
 
-    let ans = part_1(nums.iter().copied());
-    println!("{}", ans);
+impl Solution for Day1 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1(read_input(input.as_bytes()).into_iter()).into()
+    }
 
-    let ans = part_2(nums.into_iter());
-    println!("{}", ans);
+    fn part_2(&self, input: &str) -> Answer {
+        part_2(read_input(input.as_bytes()).into_iter()).into()
+    }
 }
 
 fn part_1(nums: impl Iterator<Item=u32>) -> u32 {
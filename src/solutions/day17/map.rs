@@ -1,10 +1,9 @@
 use std::fmt;
 use std::ops::Index;
-use crate::solutions::day17::map::geometry::{Dir, Point, DIRS};
+use crate::solutions::geometry::{Dir, Point, DIRS};
 use crate::solutions::day17::map::Tile::{Water, Land};
 
 mod build_map;
-mod geometry;
 mod part_1;
 mod part_2;
 
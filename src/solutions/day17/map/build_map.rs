@@ -1,8 +1,8 @@
-use crate::intcode_computer::IntcodeComputer;
+use crate::intcode_computer::{AsciiIo, IntcodeComputer};
 use crate::solutions::day17::map::{Map, Robot, Tile};
 use crate::solutions::day17::map::Tile::{Land, Water};
-use crate::solutions::day17::map::geometry::Dir::{Up, Down, Left, Right};
-use crate::solutions::day17::map::geometry::Point;
+use crate::solutions::geometry::Dir::{Up, Down, Left, Right};
+use crate::solutions::geometry::Point;
 
 /// Run the "ASCII" program to get the initial state of the map and robot.
 pub fn build_map(ascii_prog: Vec<i64>) -> (Map, Robot) {
@@ -11,13 +11,14 @@ pub fn build_map(ascii_prog: Vec<i64>) -> (Map, Robot) {
         robot: None,
     };
 
-    let input = || panic!();
-    let output = |x| {
-        assert!(0 <= x && x < 256);
-        builder.update(x as u8 as char);
-    };
+    // This program never asks for input.
+    let mut text = vec![];
+    let values = AsciiIo::scripted("", &mut text).run(&mut IntcodeComputer::new(ascii_prog)).unwrap();
+    assert!(values.is_empty());
 
-    IntcodeComputer::new(ascii_prog).io(input, output).run();
+    for c in text {
+        builder.update(c as char);
+    }
 
     builder.finish()
 }
@@ -1,4 +1,4 @@
-use crate::solutions::day17::map::geometry::Point;
+use crate::solutions::geometry::Point;
 use crate::solutions::day17::map::Map;
 use crate::solutions::day17::map::Tile::Land;
 
@@ -1,19 +1,29 @@
 use std::{io, thread};
+use std::io::Write;
 use std::time::Duration;
-use crate::intcode_computer::{IntcodeComputer, read_intcode_program};
+use crate::intcode_computer::{AsciiIo, IntcodeComputer, read_intcode_program};
+use crate::solutions::{Answer, Solution};
 use crate::solutions::day17::map::{Map, build_map, Robot, Step};
 use crate::solutions::day17::map::Step::{MoveForward, TurnLeft, TurnRight};
 
 mod map;
 
-pub fn main() {
-    let prog = read_intcode_program(io::stdin().lock());
-    let (map, _robot) = build_map(prog.clone());
+#[derive(Default)]
+pub struct Day17;
 
-    println!("{}", part_1(&map));
+impl Solution for Day17 {
+    fn part_1(&self, input: &str) -> Answer {
+        let (map, _robot) = build_map(read_intcode_program(input.as_bytes()));
+        part_1(&map).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        let prog = read_intcode_program(input.as_bytes());
 
-    // part_2_traverse(&map, _robot);
-    println!("{}", part_2_answer(prog, false));
+        // let (map, robot) = build_map(prog.clone());
+        // part_2_traverse(&map, robot);
+        part_2_answer(prog, false).into()
+    }
 }
 
 fn part_1(map: &Map) -> isize {
@@ -84,39 +94,53 @@ fn part_2_answer(mut ascii_prog: Vec<i64>, print_output: bool) -> i64 {
         "n\n"
     };
 
-    let mut input = ASCII_INPUT.chars().chain(y_or_n.chars());
-    let mut output_buf = String::new();
-    let mut ans = None;
-
-    IntcodeComputer::new(ascii_prog).io(
-        || input.next().unwrap() as i64,
-        |x| {
-            if 0 <= x && x < 256 {
-                let c = x as u8 as char;
-
-                // On every blank line:
-                if c == '\n' && output_buf.chars().last() == Some('\n') {
-                    // Flush output to the screen, then pause very briefly between frames.
-                    if print_output {
-                        println!("{}", output_buf);
-                        thread::sleep(Duration::from_millis(50));
-                    }
-
-                    output_buf.clear();
-                } else {
-                    output_buf.push(c);
-                }
+    let script = format!("{}{}", ASCII_INPUT, y_or_n);
+    let mut animation = Animation::default();
+
+    let output: Box<dyn Write> = if print_output {
+        Box::new(&mut animation)
+    } else {
+        Box::new(io::sink())
+    };
+    let values = AsciiIo::scripted(&script, output).run(&mut IntcodeComputer::new(ascii_prog)).unwrap();
+
+    // Any remaining output?
+    if print_output {
+        print!("{}", animation.frame);
+    }
+
+    assert_eq!(values.len(), 1);
+    values[0]
+}
+
+/// Prints the robot's camera feed a frame at a time.
+#[derive(Default)]
+struct Animation {
+    /// The frame being drawn.
+    frame: String,
+}
+
+impl Write for Animation {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &b in buf {
+            let c = b as char;
+
+            // On every blank line:
+            if c == '\n' && self.frame.ends_with('\n') {
+                // Flush output to the screen, then pause very briefly between frames.
+                println!("{}", self.frame);
+                thread::sleep(Duration::from_millis(50));
+
+                self.frame.clear();
             } else {
-                assert!(ans.is_none());
-                ans = Some(x);
+                self.frame.push(c);
             }
         }
-    ).run();
 
-    // Any remaining output?
-    if print_output {
-        print!("{}", output_buf);
+        Ok(buf.len())
     }
 
-    ans.unwrap()
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
@@ -1,26 +1,33 @@
-use std::io;
-use crate::intcode_computer::{IntcodeComputer, read_intcode_program};
+use crate::intcode_computer::{Event, IntcodeComputer, read_intcode_program};
+use crate::solutions::{Answer, Solution};
 
-pub fn main() {
-    let prog = read_intcode_program(io::stdin().lock());
+#[derive(Default)]
+pub struct Day19;
 
-    println!("{}", part_1(prog.clone())); // 211
+impl Solution for Day19 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1(read_intcode_program(input.as_bytes())).into()
+    }
 
+    fn part_2(&self, input: &str) -> Answer {
+        part_2(read_intcode_program(input.as_bytes())).into()
+    }
+}
+
+fn part_1(prog: Vec<i64>) -> usize {
+    Grid::new(prog, 50, 50).num_trues()
+}
+
+fn part_2(prog: Vec<i64>) -> usize {
     // Just brute force it ... :)
-    // Takes about 30 seconds to run on my pc.
+    // Takes about 10 seconds to run on my pc.
     let grid = Grid::new(prog, 1_000, 1_500);
-    dbg!(grid.num_trues());
-    dbg!(grid.row_num_trues(grid.grid.len() - 1));
-    dbg!(grid.col_num_trues(grid.grid[0].len() - 1));
 
     // We're probably supposed to do this 'on-line' instead of building the whole grid greedily
     // up-front. Specifically, we should traverse the bottom-left edge of the beam, and for each point on that
     // edge, check the diagonal's length.
-    dbg!(grid.find_large_square(100)); // 807, 1006
-}
-
-fn part_1(prog: Vec<i64>) -> usize {
-    Grid::new(prog, 50, 50).num_trues()
+    let (x, y) = grid.find_large_square(100).expect("Grid too small");
+    x * 10_000 + y
 }
 
 struct Grid {
@@ -31,17 +38,25 @@ impl Grid {
     fn new(prog: Vec<i64>, num_rows: usize, num_cols: usize) -> Self {
         let mut grid = vec![vec![false; num_cols]; num_rows];
 
+        // Every run starts out the same way, up until the drone asks for coordinates.
+        // So we only do that part once, and then rewind to there for each point.
+        let mut drone = IntcodeComputer::new(prog);
+        assert_eq!(drone.run_until_event().unwrap(), Event::NeedsInput);
+        let start = drone.snapshot();
+
         for i in 0..num_rows {
             for j in 0..num_cols {
-                let mut input = [i as i64, j as i64].into_iter();
-                let output = |x| {
-                    debug_assert!(x == 0 || x == 1);
-                    if x != 0 {
-                        grid[i][j] = true;
-                    }
+                drone.restore(&start);
+                drone.provide_input(i as i64);
+                drone.provide_input(j as i64);
+
+                let x = match drone.run_until_event().unwrap() {
+                    Event::Output(x) => x,
+                    event => panic!("Expected output, got {:?}", event),
                 };
 
-                IntcodeComputer::new(prog.clone()).io(|| input.next().unwrap(), output).run();
+                debug_assert!(x == 0 || x == 1);
+                grid[i][j] = x != 0;
             }
         }
 
@@ -84,16 +99,6 @@ impl Grid {
     fn num_trues(&self) -> usize {
         count_trues(self.grid.iter().flat_map(|row| row.iter().copied()))
     }
-
-    fn row_num_trues(&self, i: usize) -> usize {
-        let row = (0..self.grid[0].len()).map(|j| self.grid[i][j]);
-        count_trues(row)
-    }
-
-    fn col_num_trues(&self, j: usize) -> usize {
-        let col = (0..self.grid.len()).map(|i| self.grid[i][j]);
-        count_trues(col)
-    }
 }
 
 fn count_trues(iter: impl Iterator<Item=bool>) -> usize {
@@ -1,24 +1,31 @@
 use crate::intcode_computer::{read_intcode_program, IntcodeComputer};
-use std::io;
+use crate::solutions::{Answer, Solution};
 
-pub fn main() {
-    let nums = read_intcode_program(io::stdin().lock());
+#[derive(Default)]
+pub struct Day2;
 
-    let ans = part_1(nums.clone());
-    println!("{}", ans);
+impl Solution for Day2 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1(read_intcode_program(input.as_bytes())).into()
+    }
 
-    let (noun, verb) = part_2(nums);
-    println!("{}", noun * 100 + verb);
+    fn part_2(&self, input: &str) -> Answer {
+        let (noun, verb) = part_2(read_intcode_program(input.as_bytes()));
+        (noun * 100 + verb).into()
+    }
 }
 
 fn part_1(nums: Vec<i64>) -> i64 {
-    IntcodeComputer::new(nums).run_noun_verb(12, 2)
+    IntcodeComputer::new(nums).run_noun_verb(12, 2).unwrap()
 }
 
+#[allow(clippy::inconsistent_digit_grouping)] // It's a date.
 fn part_2(nums: Vec<i64>) -> (i64, i64) {
+    let computer = IntcodeComputer::new(nums);
+
     for noun in 0..100 {
         for verb in 0..100 {
-            if IntcodeComputer::new(nums.clone()).run_noun_verb(noun, verb) == 1969_07_20 {
+            if computer.clone().run_noun_verb(noun, verb).unwrap() == 1969_07_20 {
                 return (noun, verb);
             }
         }
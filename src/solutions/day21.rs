@@ -1,24 +1,26 @@
-use std::collections::VecDeque;
 use std::io;
-use std::io::BufReader;
-use crate::intcode_computer::{IntcodeComputer, read_intcode_program};
+use crate::intcode_computer::{AsciiIo, IntcodeComputer, read_intcode_program};
+use crate::solutions::{Answer, Solution};
 
 /*
 The logic that worked for part 1 was:
 j := !a || (!c && d)
 
-Which I encoded as:
-NOT C J
-AND D J
-NOT A T
-OR T J
-
 ---
 
 For part 2, the following ended up working:
 j := !a || ( (!b || !c) && d && h )
+ */
 
-Encoded as:
+const PART_1_SCRIPT: &str = "\
+NOT C J
+AND D J
+NOT A T
+OR T J
+WALK
+";
+
+const PART_2_SCRIPT: &str = "\
 OR B J
 AND C J
 NOT J J
@@ -26,33 +28,28 @@ AND D J
 AND H J
 NOT A T
 OR T J
- */
+RUN
+";
+
+#[derive(Default)]
+pub struct Day21;
+
+impl Solution for Day21 {
+    fn part_1(&self, input: &str) -> Answer {
+        solve(input, PART_1_SCRIPT).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        solve(input, PART_2_SCRIPT).into()
+    }
+}
+
+/// Returns the amount of hull damage reported by the droid.
+fn solve(input: &str, script: &str) -> i64 {
+    let mut computer = IntcodeComputer::new(read_intcode_program(input.as_bytes()));
 
-pub fn main() {
-    let input = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/inputs/21"));
-    let prog = read_intcode_program(BufReader::new(input.as_bytes()));
-
-    let mut input_buf = VecDeque::new();
-    let input = || {
-        // Grab a line of input all-at-once from stdin.
-        if input_buf.is_empty() {
-            let mut buf = String::new();
-            io::stdin().read_line(&mut buf).unwrap();
-
-            input_buf.extend(buf.chars());
-        }
-
-        // Yield up chars one-at-a-time to the intcode program.
-        input_buf.pop_front().unwrap() as i64
-    };
-
-    let output = |x| {
-        if 0 <= x && x < 256 {
-            print!("{}", x as u8 as char);
-        } else {
-            print!("\n*** NON-ASCII VALUE: {} ***\n", x);
-        }
-    };
-
-    IntcodeComputer::new(prog).io(input, output).run();
+    // If the droid falls into space, the only output is ASCII: an animation of its last moments.
+    let values = AsciiIo::scripted(script, io::sink()).run(&mut computer).unwrap();
+    assert_eq!(values.len(), 1, "The droid didn't make it.");
+    values[0]
 }
@@ -17,7 +17,7 @@ impl Technique {
         if line == "deal into new stack" {
             Reverse
         } else {
-            let last_word = line.split(' ').rev().next().unwrap();
+            let last_word = line.split(' ').next_back().unwrap();
 
             if line.starts_with("cut ") {
                 let i: isize = last_word.parse().unwrap();
@@ -1,4 +1,4 @@
-use std::io;
+use crate::solutions::{Answer, Solution};
 use crate::solutions::day22::input::read_input;
 use crate::solutions::day22::part1::part_1;
 use crate::solutions::day22::part2::part_2;
@@ -7,9 +7,15 @@ mod input;
 mod part1;
 mod part2;
 
-pub fn main() {
-    let shuffle = read_input(io::stdin().lock());
+#[derive(Default)]
+pub struct Day22;
 
-    println!("{}", part_1(&shuffle));
-    println!("{}", part_2(&shuffle));
+impl Solution for Day22 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1(&read_input(input.as_bytes())).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        part_2(&read_input(input.as_bytes())).into()
+    }
 }
@@ -37,7 +37,7 @@ impl Deck {
         if offset >= 0 {
             self.cards.rotate_left(offset as usize);
         } else {
-            self.cards.rotate_right(offset.abs() as usize);
+            self.cards.rotate_right(offset.unsigned_abs());
         }
     }
 
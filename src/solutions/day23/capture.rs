@@ -0,0 +1,117 @@
+use std::io::{self, BufRead, Write};
+use crate::solutions::day23::Packet;
+
+/// A log of everything that happened on the network, e.g.:
+///
+/// ```text
+/// # time src dest x y
+/// 12 3 255 47 1000
+/// 980 idle
+/// 980 255 0 47 1000
+/// ```
+///
+/// Times are logical: turns taken so far when simulating, or a sequence number when threaded.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Capture {
+    pub records: Vec<Record>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Record {
+    Packet { time: u64, src: i64, dest: i64, packet: Packet },
+    /// Every CPU was waiting on an empty queue.
+    Idle { time: u64 },
+}
+
+const HEADER: &str = "# time src dest x y";
+
+impl Capture {
+    /// Record a packet, timestamped with the number of records so far.
+    pub fn push_packet(&mut self, src: i64, dest: i64, packet: Packet) {
+        let time = self.records.len() as u64;
+        self.records.push(Record::Packet { time, src, dest, packet });
+    }
+
+    /// Record the network going idle, timestamped with the number of records so far.
+    pub fn push_idle(&mut self) {
+        let time = self.records.len() as u64;
+        self.records.push(Record::Idle { time });
+    }
+
+    pub fn write(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "{}", HEADER)?;
+
+        for record in &self.records {
+            match *record {
+                Record::Packet { time, src, dest, packet } => {
+                    writeln!(w, "{} {} {} {} {}", time, src, dest, packet.x, packet.y)?
+                }
+                Record::Idle { time } => writeln!(w, "{} idle", time)?,
+            }
+        }
+
+        w.flush()
+    }
+
+    /// Errors are `io::ErrorKind::InvalidData` if the log is corrupted.
+    pub fn read(r: impl BufRead) -> io::Result<Self> {
+        let mut records = vec![];
+
+        for line in r.lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Invalid record: {}", line));
+            let words: Vec<_> = line.split_whitespace().collect();
+
+            let record = match words[..] {
+                [time, "idle"] => Record::Idle { time: time.parse().map_err(|_| invalid())? },
+                [time, src, dest, x, y] => {
+                    let num = |s: &str| s.parse().map_err(|_| invalid());
+
+                    Record::Packet {
+                        time: time.parse().map_err(|_| invalid())?,
+                        src: num(src)?,
+                        dest: num(dest)?,
+                        packet: Packet { x: num(x)?, y: num(y)? },
+                    }
+                }
+                _ => return Err(invalid()),
+            };
+
+            records.push(record);
+        }
+
+        Ok(Self { records })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read() {
+        let capture = Capture {
+            records: vec![
+                Record::Packet { time: 12, src: 3, dest: 255, packet: Packet { x: 47, y: -1000 } },
+                Record::Idle { time: 980 },
+                Record::Packet { time: 980, src: 255, dest: 0, packet: Packet { x: 47, y: -1000 } },
+            ],
+        };
+
+        let mut log = vec![];
+        capture.write(&mut log).unwrap();
+        assert_eq!(String::from_utf8(log.clone()).unwrap(), "\
+# time src dest x y
+12 3 255 47 -1000
+980 idle
+980 255 0 47 -1000
+");
+
+        assert_eq!(Capture::read(&log[..]).unwrap(), capture);
+        assert!(Capture::read("1 2 3\n".as_bytes()).is_err());
+    }
+}
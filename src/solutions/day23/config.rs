@@ -0,0 +1,60 @@
+use std::str::FromStr;
+use crate::solutions::day23::Packet;
+
+/// The shape of the network. The default is the one from the puzzle.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Config {
+    /// CPUs have addresses `0..num_cpus`.
+    pub num_cpus: usize,
+    pub nat_addr: i64,
+    /// What to do with packets sent anywhere else.
+    pub unknown_addrs: UnknownAddrs,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnknownAddrs {
+    Drop,
+    /// Drop, and say so on stderr.
+    Log,
+    /// Stop the network, and fail.
+    Error,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { num_cpus: 50, nat_addr: 255, unknown_addrs: UnknownAddrs::Error }
+    }
+}
+
+impl Config {
+    pub fn is_cpu(&self, addr: i64) -> bool {
+        0 <= addr && addr < self.num_cpus as i64
+    }
+
+    /// Called for packets sent to neither a CPU nor the NAT. Fails if the network should stop.
+    pub fn unknown_addr(&self, src: i64, dest: i64, packet: Packet) -> Result<(), String> {
+        match self.unknown_addrs {
+            UnknownAddrs::Drop => {}
+            UnknownAddrs::Log => eprintln!("Dropped packet from {} to unknown address {}: {:?}", src, dest, packet),
+            UnknownAddrs::Error => return Err(format!("{} sent a packet to unknown address {}: {:?}", src, dest, packet)),
+        }
+
+        Ok(())
+    }
+}
+
+/// E.g. if every packet goes to an unknown address, and gets dropped.
+pub const IDLE_BEFORE_NAT: &str = "The network went idle before sending anything to the NAT";
+
+impl FromStr for UnknownAddrs {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(Self::Drop),
+            "log" => Ok(Self::Log),
+            "error" => Ok(Self::Error),
+            _ => Err(format!("Expected drop, log or error, not {:?}", s)),
+        }
+    }
+}
@@ -1,10 +1,13 @@
-use std::cell::RefCell;
-use std::rc::Rc;
+use std::ops::ControlFlow;
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{Receiver, Sender};
 use itertools::Itertools;
-use crate::intcode_computer::IntcodeComputer;
-use crate::solutions::day23::{NAT_ADDR, NUM_CPUS, Packet};
+use crate::intcode_computer::{Event, IntcodeComputer};
+use crate::solutions::day23::Packet;
+use crate::solutions::day23::capture::Capture;
+use crate::solutions::day23::config::Config;
 use crate::solutions::day23::deadlock::ActivityCount;
+use crate::solutions::day23::shutdown::Shutdown;
 
 /// A networked computer, running the NIC software.
 pub struct CPU {
@@ -20,6 +23,12 @@ pub struct CPU {
     /// If this goes high enough, we'll assume that this CPU is currently blocked, and won't
     /// send any more messages until it receives one first.
     num_consecutive_polls: u64,
+
+    /// Words of an outgoing packet that the NIC hasn't finished writing yet.
+    outbound_packet: Vec<i64>,
+
+    /// Set when we have our answer, and the CPUs should stop.
+    shutdown: Shutdown,
 }
 
 /// Handles to send packets to each computer.
@@ -27,74 +36,106 @@ pub struct CPU {
 pub struct Network {
     pub cpus: Vec<Sender<Packet>>,
     pub nat: Sender<Packet>,
+    /// Where to record packets, if anywhere.
+    pub capture: Option<Arc<Mutex<Capture>>>,
+    pub config: Config,
+}
+
+impl Network {
+    /// Send a packet to a CPU or the NAT. Packets to anywhere else are handled according to
+    /// `config.unknown_addrs`.
+    pub fn send(&self, src: i64, dest: i64, packet: Packet) -> Result<(), String> {
+        if let Some(capture) = &self.capture {
+            capture.lock().unwrap().push_packet(src, dest, packet);
+        }
+
+        let sender = if dest == self.config.nat_addr {
+            &self.nat
+        } else if self.config.is_cpu(dest) {
+            &self.cpus[dest as usize]
+        } else {
+            return self.config.unknown_addr(src, dest, packet);
+        };
+
+        // Fails if the destination has already shut down, in which case nobody cares.
+        let _ = sender.send(packet);
+        Ok(())
+    }
 }
 
 impl CPU {
-    pub fn new(id: usize, inbound_msgs: Receiver<Packet>, network: Network, activity_count: ActivityCount) -> Self {
+    pub fn new(id: usize, inbound_msgs: Receiver<Packet>, network: Network, activity_count: ActivityCount, shutdown: Shutdown) -> Self {
         let num_consecutive_polls = 0;
-        Self { id, inbound_msgs, network, activity_count, num_consecutive_polls }
+        let outbound_packet = Vec::with_capacity(3);
+        Self { id, inbound_msgs, network, activity_count, num_consecutive_polls, outbound_packet, shutdown }
     }
 
-    pub fn run(self, nic_program: Vec<i64>) {
-        // CPU input and output both need `&mut self`.
-        // (They won't be called concurrently by the IntcodeComputer, so this is fine.)
-        let self1 = Rc::new(RefCell::new(self));
-        let self2 = Rc::clone(&self1);
-
-        IntcodeComputer::new(nic_program).io(
-            Self::provide_cpu_input(self1),
-            Self::handle_cpu_output(self2),
-        ).run();
+    /// `computer` should be running the NIC software from the start. (It's created by the
+    /// caller, rather than on this CPU's thread, so that `count_instructions` sees it.)
+    pub fn run(mut self, mut computer: IntcodeComputer) {
+        // First input instruction is always the CPU's own id.
+        computer.provide_input(self.id as i64);
+
+        loop {
+            match computer.run_until_event().unwrap() {
+                Event::NeedsInput => {
+                    if self.provide_cpu_input(&mut computer).is_break() {
+                        return;
+                    }
+                }
+                Event::Output(val) => {
+                    if let Err(msg) = self.handle_cpu_output(val) {
+                        self.shutdown.fail(msg);
+                        return;
+                    }
+                }
+                Event::Halted => return,
+            }
+        }
     }
 
-    /// Returns a closure that gets called when the CPU requests input.
+    /// Called when the CPU requests input.
     ///
-    /// Handles incoming messages.
-    fn provide_cpu_input(self_: Rc<RefCell<Self>>) -> impl FnMut() -> i64 {
-        let mut id = Some(self_.borrow().id as i64);
-        let mut inbound_packet_y = None;
-
-        move || {
-            let mut self_ = self_.borrow_mut();
+    /// Handles incoming messages. Breaks if the CPU should shut down.
+    fn provide_cpu_input(&mut self, computer: &mut IntcodeComputer) -> ControlFlow<()> {
+        if self.shutdown.is_signalled() {
+            return ControlFlow::Break(());
+        }
 
-            // First input instruction is always the CPU's own id.
-            if let Some(id) = id.take() {
-                return id;
+        let packet = if self.num_consecutive_polls < Self::INACTIVE_THRESH {
+            // Under normal circumstances, we don't block waiting for input.
+            match self.inbound_msgs.try_recv() {
+                Ok(packet) => packet,
+                _ => {
+                    self.block();
+                    computer.provide_input(-1);
+                    return ControlFlow::Continue(());
+                }
             }
-
-            // If there's a partially-digested inbound packet, use that.
-            if let Some(y) = inbound_packet_y.take() {
-                return y;
+        } else {
+            // As an optimization, if the current CPU is declared to be stuck,
+            // we *do* block waiting for a message, so we're not spinning and
+            // wasting cycles.
+            //
+            // We still wake up if we're told to shut down, though.
+            match self.shutdown.recv(&self.inbound_msgs) {
+                Some(packet) => packet,
+                None => return ControlFlow::Break(()),
             }
+        };
 
-            let packet = if self_.num_consecutive_polls < Self::INACTIVE_THRESH {
-                // Under normal circumstances, we don't block waiting for input.
-                match self_.inbound_msgs.try_recv() {
-                    Ok(packet) => packet,
-                    _ => {
-                        self_.block();
-                        return -1;
-                    }
-                }
-            } else {
-                // As an optimization, if the current CPU is declared to be stuck,
-                // we *do* block waiting for a message, so we're not spinning and
-                // wasting cycles.
-                self_.inbound_msgs.recv().unwrap()
-            };
-
-            // Note that we unblock the CPU (possibly incrementing activity_count) _before_
-            // "consuming" the message (decrementing activity_count).
-            //
-            // This ensures the activity_count doesn't hit 0 accidentally,
-            // when the system isn't actually deadlocked.
-            self_.unblock();
-            self_.activity_count.decr(); // -1 message in flight.
-
-            // Save the second half of the packet for later.
-            inbound_packet_y = Some(packet.y);
-            packet.x
-        }
+        // Note that we unblock the CPU (possibly incrementing activity_count) _before_
+        // "consuming" the message (decrementing activity_count).
+        //
+        // This ensures the activity_count doesn't hit 0 accidentally,
+        // when the system isn't actually deadlocked.
+        self.unblock();
+        self.activity_count.decr(); // -1 message in flight.
+
+        computer.provide_input(packet.x);
+        computer.provide_input(packet.y);
+
+        ControlFlow::Continue(())
     }
 
     /// How many consecutive polls until we consider the CPU blocked.
@@ -125,36 +166,28 @@ impl CPU {
         self.num_consecutive_polls = 0;
     }
 
-    /// Returns a closure that gets called when the CPU produces output.
+    /// Called when the CPU produces output.
     ///
-    /// Handles outgoing messages.
-    fn handle_cpu_output(self_: Rc<RefCell<Self>>) -> impl FnMut(i64) {
-        let mut outbound_packet = Vec::with_capacity(3);
-
-        move |val| {
-            let mut self_ = self_.borrow_mut();
-            assert!(self_.num_consecutive_polls < Self::INACTIVE_THRESH,
-                    "CPU {} sent a message when we thought it was blocked.", self_.id);
-
-            debug_assert!(outbound_packet.len() < 3);
-            outbound_packet.push(val);
+    /// Handles outgoing messages. Fails if the network should stop.
+    fn handle_cpu_output(&mut self, val: i64) -> Result<(), String> {
+        assert!(self.num_consecutive_polls < Self::INACTIVE_THRESH,
+                "CPU {} sent a message when we thought it was blocked.", self.id);
 
-            // Finished packet; send it.
-            if outbound_packet.len() == 3 {
-                let (addr, x, y) = outbound_packet.drain(..).collect_tuple().unwrap();
+        debug_assert!(self.outbound_packet.len() < 3);
+        self.outbound_packet.push(val);
 
-                let dest = if 0 <= addr && addr < NUM_CPUS as i64 {
-                    // Note that messages to the NAT don't count towards `activity_count`.
-                    self_.activity_count.incr(); // +1 message in flight.
+        // Finished packet; send it.
+        if self.outbound_packet.len() == 3 {
+            let (addr, x, y) = self.outbound_packet.drain(..).collect_tuple().unwrap();
 
-                    &mut self_.network.cpus[addr as usize]
-                } else {
-                    assert_eq!(addr, NAT_ADDR);
-                    &mut self_.network.nat
-                };
-
-                dest.send(Packet { x, y }).unwrap();
+            // Note that messages to the NAT (or nowhere) don't count towards `activity_count`.
+            if self.network.config.is_cpu(addr) {
+                self.activity_count.incr(); // +1 message in flight.
             }
+
+            self.network.send(self.id as i64, addr, Packet { x, y })?;
         }
+
+        Ok(())
     }
 }
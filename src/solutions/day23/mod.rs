@@ -1,56 +1,465 @@
-use std::{io, iter, thread};
-use std::sync::mpsc;
-use crate::intcode_computer::read_intcode_program;
+use std::{iter, thread};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek};
+use std::ops::ControlFlow;
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+use crate::intcode_computer::{read_intcode_program, IntcodeComputer};
+use crate::solutions::{Answer, Part, Solution};
+use crate::solutions::day23::capture::{Capture, Record};
+use crate::solutions::day23::config::{Config, IDLE_BEFORE_NAT};
 use crate::solutions::day23::cpu::{CPU, Network};
 use crate::solutions::day23::nat::NAT;
+use crate::solutions::day23::policy::{Dest, Forward, NatPolicy, Stats, StopAfter};
+use crate::solutions::day23::shutdown::Shutdown;
+use crate::solutions::day23::simulator::{NetEvent, Simulator};
 
+mod capture;
+mod config;
 mod cpu;
 mod nat;
 mod deadlock;
+mod policy;
+mod shutdown;
+mod simulator;
 
-const NUM_CPUS: usize = 50;
-const NAT_ADDR: i64 = 255;
-
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Packet {
     x: i64,
     y: i64,
 }
 
-pub fn main() {
-    let prog = read_intcode_program(io::stdin().lock());
+/// Options (see `configure`):
+///
+/// `--threads`: Run each CPU on its own thread, instead of simulating the network on one thread.
+///
+/// `--capture <file>`: Record part 2's network traffic to a file.
+///
+/// `--replay <file>`: Work out the answers from a recording, instead of running the network.
+///
+/// The shape of the network (see `config`):
+///
+/// `--cpus <n>`: How many CPUs there are (50 by default).
+///
+/// `--nat-addr <addr>`: The NAT's address (255 by default).
+///
+/// `--unknown-addrs drop|log|error`: What to do with packets sent anywhere else (`error` by default).
+///
+/// How the NAT handles the network going idle in part 2 (see `policy`):
+///
+/// `--nat-broadcast`: Wake up every CPU, instead of just CPU 0. With our input, the same y never
+/// comes up twice in a row, so combine this with `--nat-max-wakeups`.
+///
+/// `--nat-dest <addr>`: Wake up CPU `addr`, instead of CPU 0.
+///
+/// `--nat-max-wakeups <k>`: Give up after `k` wakeups.
+///
+/// `--nat-stats`: Print some statistics about the NAT's traffic when done.
+#[derive(Default)]
+pub struct Day23 {
+    threaded: bool,
+    /// Created up front, so that we find out about a bad path before running the network.
+    capture_file: Option<File>,
+    replay: Option<Capture>,
+    config: Config,
+    nat_dest: usize,
+    broadcast: bool,
+    max_wakeups: Option<usize>,
+    stats: bool,
+}
+
+impl Solution for Day23 {
+    fn part_1(&self, input: &str) -> Answer {
+        self.try_part(Part::One, input).unwrap_or_else(|msg| panic!("{}", msg))
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        self.try_part(Part::Two, input).unwrap_or_else(|msg| panic!("{}", msg))
+    }
 
-    println!("{}", solve(prog.clone(), false));
-    println!("{}", solve(prog, true));
+    /// Fails if a packet goes to an unknown address, with `--unknown-addrs error`.
+    fn try_part(&self, part: Part, input: &str) -> Result<Answer, String> {
+        match part {
+            Part::One => self.try_part_1(input),
+            Part::Two => self.try_part_2(input),
+        }.map(Answer::from)
+    }
+
+    fn configure(&mut self, options: &[String]) -> Result<(), String> {
+        let mut options = options.iter();
+        while let Some(opt) = options.next() {
+            let mut arg = || options.next().ok_or_else(|| format!("{} needs an argument", opt));
+            let mut num = || arg()?.parse().map_err(|_| format!("{} needs a number", opt));
+
+            match opt.as_str() {
+                "--threads" => self.threaded = true,
+                "--capture" => {
+                    let path = arg()?;
+                    let file = File::create(path).map_err(|e| format!("Can't create {}: {}", path, e))?;
+                    self.capture_file = Some(file);
+                }
+                "--replay" => {
+                    let path = arg()?;
+                    let file = File::open(path).map_err(|e| format!("Can't open {}: {}", path, e))?;
+                    let capture = Capture::read(BufReader::new(file)).map_err(|e| format!("Can't read {}: {}", path, e))?;
+                    self.replay = Some(capture);
+                }
+                "--cpus" => self.config.num_cpus = num()?,
+                "--nat-addr" => self.config.nat_addr = arg()?.parse().map_err(|_| format!("{} needs a number", opt))?,
+                "--unknown-addrs" => self.config.unknown_addrs = arg()?.parse()?,
+                "--nat-broadcast" => self.broadcast = true,
+                "--nat-dest" => self.nat_dest = num()?,
+                "--nat-max-wakeups" => self.max_wakeups = Some(num()?),
+                "--nat-stats" => self.stats = true,
+                _ => return Err(format!("Unknown option: {}", opt)),
+            }
+        }
+
+        let config = &self.config;
+        if config.num_cpus == 0 {
+            Err("The network needs at least one CPU".to_string())
+        } else if config.is_cpu(config.nat_addr) {
+            Err(format!("The NAT's address is taken by a CPU: {}", config.nat_addr))
+        } else if self.nat_dest >= config.num_cpus {
+            Err(format!("No CPU at address {}", self.nat_dest))
+        } else if self.max_wakeups == Some(0) {
+            Err("The NAT needs at least one wakeup to have anything to report".to_string())
+        } else {
+            Ok(())
+        }
+    }
 }
 
-fn solve(prog: Vec<i64>, part_2: bool) -> i64 {
+type Solver = fn(Vec<i64>, bool, Option<&mut Capture>, &mut dyn NatPolicy, Config) -> Result<i64, String>;
+
+impl Day23 {
+    fn try_part_1(&self, input: &str) -> Result<i64, String> {
+        // Part 1 is over before the NAT does anything.
+        let mut nat = Forward::default();
+
+        match &self.replay {
+            Some(capture) => Ok(replay(capture, false, &mut nat, self.config.nat_addr)),
+            None => self.solver()(read_intcode_program(input.as_bytes()), false, None, &mut nat, self.config),
+        }
+    }
+
+    fn try_part_2(&self, input: &str) -> Result<i64, String> {
+        let mut nat = self.nat_policy();
+
+        let answer = match &self.replay {
+            Some(capture) => replay(capture, true, &mut nat, self.config.nat_addr),
+            None => {
+                let prog = read_intcode_program(input.as_bytes());
+                let mut capture = self.capture_file.as_ref().map(|_| Capture::default());
+                let answer = self.solver()(prog, true, capture.as_mut(), &mut nat, self.config)?;
+
+                if let (Some(mut file), Some(capture)) = (self.capture_file.as_ref(), capture) {
+                    // Replace any earlier run's capture, e.g. when benchmarking.
+                    file.set_len(0).unwrap();
+                    file.rewind().unwrap();
+                    capture.write(BufWriter::new(file)).unwrap();
+                }
+
+                answer
+            }
+        };
+
+        if let Some(report) = nat.report() {
+            eprintln!("{}", report);
+        }
+
+        Ok(answer)
+    }
+
+    fn solver(&self) -> Solver {
+        if self.threaded { solve_threaded } else { simulate }
+    }
+
+    fn nat_policy(&self) -> Box<dyn NatPolicy> {
+        let nat_dest = if self.broadcast {
+            Dest::All { num_cpus: self.config.num_cpus }
+        } else {
+            Dest::Cpu(self.nat_dest)
+        };
+
+        let mut policy: Box<dyn NatPolicy> = Box::new(Forward::new(nat_dest));
+        if let Some(k) = self.max_wakeups {
+            policy = Box::new(StopAfter::new(policy, k));
+        }
+        if self.stats {
+            policy = Box::new(Stats::new(policy));
+        }
+
+        policy
+    }
+}
+
+/// Deterministic, single-threaded version of `solve_threaded`.
+///
+/// `nat` is only used in part 2.
+fn simulate(prog: Vec<i64>, part_2: bool, capture: Option<&mut Capture>, nat: &mut dyn NatPolicy, config: Config) -> Result<i64, String> {
+    let mut network = Simulator::new(&prog, config.num_cpus);
+    if capture.is_some() {
+        network.start_capture();
+    }
+
+    let mut received_any = false;
+
+    let answer = loop {
+        match network.run() {
+            NetEvent::Sent { src, addr, packet } => {
+                if addr != config.nat_addr {
+                    config.unknown_addr(src as i64, addr, packet)?;
+                    continue;
+                }
+
+                if !part_2 {
+                    // The magic packet.
+                    break packet.y;
+                }
+                received_any = true;
+                nat.receive(packet);
+            }
+            NetEvent::Idle if !received_any => return Err(IDLE_BEFORE_NAT.to_string()),
+            NetEvent::Idle => match nat.on_idle() {
+                ControlFlow::Continue(packets) => {
+                    for (dest, packet) in packets {
+                        network.send(config.nat_addr, dest, packet);
+                    }
+                }
+                ControlFlow::Break(y) => break y,
+            }
+        }
+    };
+
+    if let Some(capture) = capture {
+        *capture = network.take_capture().unwrap();
+    }
+
+    Ok(answer)
+}
+
+/// Run the NAT logic against recorded traffic, rather than a live network.
+fn replay(capture: &Capture, part_2: bool, nat: &mut dyn NatPolicy, nat_addr: i64) -> i64 {
+    for record in &capture.records {
+        match *record {
+            Record::Packet { dest, packet, .. } if dest == nat_addr => {
+                if !part_2 {
+                    return packet.y;
+                }
+                nat.receive(packet);
+            }
+            // Including the packets the NAT sent, since we're working those out for ourselves.
+            Record::Packet { .. } => {}
+            Record::Idle { .. } => {
+                if let ControlFlow::Break(y) = nat.on_idle() {
+                    return y;
+                }
+            }
+        }
+    }
+
+    panic!("The recording ended before the NAT had an answer.");
+}
+
+fn solve_threaded(prog: Vec<i64>, part_2: bool, capture: Option<&mut Capture>, nat: &mut dyn NatPolicy, config: Config) -> Result<i64, String> {
     // Each thread has its own queue of incoming packets.
-    let channels = iter::repeat_with(mpsc::channel).take(NUM_CPUS);
+    let channels = iter::repeat_with(mpsc::channel).take(config.num_cpus);
     let (senders, receivers): (Vec<_>, Vec<_>) = channels.into_iter().unzip();
 
     // One extra channel, for the NAT.
     let (nat_sx, nat_rx) = mpsc::channel();
 
-    let network = Network { cpus: senders, nat: nat_sx };
-    let (activity_count, deadlock_signal) = deadlock::tracker(NUM_CPUS);
+    let shared_capture = capture.is_some().then(|| Arc::new(Mutex::new(Capture::default())));
+    let network = Network { cpus: senders, nat: nat_sx, capture: shared_capture.clone(), config };
+    let (activity_count, deadlock_signal) = deadlock::tracker(config.num_cpus);
+
+    let shutdown = Shutdown::default();
 
     let cpus = receivers.into_iter().enumerate().map(|(id, rx)| {
-        CPU::new(id, rx, network.clone(), activity_count.clone())
+        CPU::new(id, rx, network.clone(), activity_count.clone(), shutdown.clone())
     });
 
-    // Note that we don't bother to join or kill the threads.
-    for cpu in cpus {
-        let prog = prog.clone();
+    let threads: Vec<_> = cpus.map(|cpu| {
+        let computer = IntcodeComputer::new(prog.clone());
+        let shutdown = shutdown.clone();
+
         thread::spawn(move || {
-            cpu.run(prog);
-        });
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| cpu.run(computer))) {
+                // Bring the rest of the network down too, rather than leave it waiting on us.
+                shutdown.signal();
+                panic::resume_unwind(payload);
+            }
+        })
+    }).collect();
+
+    let answer = if !part_2 {
+        wait_for_nat_packet(&nat_rx, &deadlock_signal, &shutdown)
+    } else {
+        NAT::new(activity_count, deadlock_signal, nat_rx, network, shutdown.clone()).run(nat)
+    };
+
+    // Each CPU stops the next time it polls for input.
+    shutdown.signal();
+    for (id, thread) in threads.into_iter().enumerate() {
+        if let Err(payload) = thread.join() {
+            eprintln!("CPU {} panicked: {}", id, panic_message(&payload));
+        }
+    }
+
+    if let (Some(capture), Some(shared)) = (capture, shared_capture) {
+        *capture = shared.lock().unwrap().clone();
+    }
+
+    if let Some(msg) = shutdown.error() {
+        return Err(msg);
     }
+    Ok(answer.expect("The network shut down before we had an answer."))
+}
+
+/// Wait for the magic packet in part 1. Fails (via `shutdown`) if the network goes idle first.
+fn wait_for_nat_packet(nat_rx: &Receiver<Packet>, deadlock: &Receiver<()>, shutdown: &Shutdown) -> Option<i64> {
+    loop {
+        match nat_rx.recv_timeout(Duration::from_millis(10)) {
+            Ok(packet) => return Some(packet.y),
+            Err(RecvTimeoutError::Timeout) if !shutdown.is_signalled() => {}
+            Err(_) => return None,
+        }
+
+        if deadlock.try_recv().is_ok() {
+            // CPUs send before they block, so the packet would already be here.
+            return match nat_rx.try_recv() {
+                Ok(packet) => Some(packet.y),
+                Err(_) => {
+                    shutdown.fail(IDLE_BEFORE_NAT.to_string());
+                    None
+                }
+            };
+        }
+    }
+}
 
-    if !part_2 {
-        // Wait for the magic packet.
-        nat_rx.recv().unwrap().y
+fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
     } else {
-        NAT::new(activity_count, deadlock_signal, nat_rx, network).run()
+        "(unknown)"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+    use itertools::Itertools;
+    use super::*;
+    use crate::intcode_computer::assemble;
+    use crate::solutions::day23::config::UnknownAddrs;
+
+    /// Sends (1, 2) to address 300, then (id, 7) to address 255, then just keeps polling.
+    const NIC: &str = "
+                in [id]
+                out #300
+                out #1
+                out #2
+                out #255
+                out [id]
+                out #7
+        loop:   in [tmp]
+                jt #1, #loop
+        id:     data 0
+        tmp:    data 0
+    ";
+
+    #[test]
+    fn test_unknown_addrs() {
+        let prog = assemble(NIC).unwrap();
+        let config = Config { num_cpus: 2, unknown_addrs: UnknownAddrs::Drop, ..Config::default() };
+
+        assert_eq!(simulate(prog.clone(), false, None, &mut Forward::default(), config), Ok(7));
+
+        // Also checks that the threads shut down once we have the answer (or this would hang).
+        assert_eq!(solve_threaded(prog, false, None, &mut Forward::default(), config), Ok(7));
+    }
+
+    #[test]
+    fn test_unknown_addrs_error() {
+        let prog = assemble(NIC).unwrap();
+
+        for solver in [simulate, solve_threaded] {
+            let err = solver(prog.clone(), false, None, &mut Forward::default(), Config::default()).unwrap_err();
+            assert!(err.contains("sent a packet to unknown address 300"), "{}", err);
+        }
+
+        // Rather than panic.
+        assert!(Day23::default().try_part(Part::One, &prog.iter().join(",")).is_err());
+    }
+
+    #[test]
+    fn test_idle_before_nat() {
+        // Only ever sends to address 300.
+        let prog = assemble("out #300\nout #1\nout #2\nloop: in [x]\njt #1, #loop\nx: data 0").unwrap();
+        let config = Config { num_cpus: 2, unknown_addrs: UnknownAddrs::Drop, ..Config::default() };
+
+        for solver in [simulate, solve_threaded] {
+            for part_2 in [false, true] {
+                let answer = solver(prog.clone(), part_2, None, &mut Forward::default(), config);
+                assert_eq!(answer, Err(IDLE_BEFORE_NAT.to_string()));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "shut down before we had an answer")]
+    fn test_threads_panic() {
+        // Every CPU panics (on an invalid opcode) before it gets as far as the NAT. We shouldn't
+        // wait for them forever.
+        let prog = assemble("in [id]\ndata 42\nid: data 0").unwrap();
+        solve_threaded(prog, false, None, &mut Forward::default(), Config::default()).unwrap();
+    }
+
+    #[test]
+    fn test_capture_twice() {
+        let path = env::temp_dir().join(format!("advent-2019-day23-capture-{}", process::id()));
+        let path = path.to_str().unwrap();
+        let input = assemble(NIC).unwrap().iter().join(",");
+
+        let mut day = Day23::default();
+        let options = ["--cpus", "2", "--unknown-addrs", "drop", "--capture", path];
+        day.configure(&options.map(String::from)).unwrap();
+        assert_eq!(day.part_2(&input), Answer::Number(7));
+        assert_eq!(day.part_2(&input), Answer::Number(7));
+
+        let capture = fs::read_to_string(path).unwrap();
+        assert_eq!(capture.matches("# time").count(), 1);
+
+        let mut day = Day23::default();
+        day.configure(&["--replay", path].map(String::from)).unwrap();
+        assert_eq!(day.part_2(""), Answer::Number(7));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_replay() {
+        let log = "\
+# time src dest x y
+1 3 255 10 20
+2 idle
+2 255 0 10 20
+5 4 255 11 21
+7 idle
+7 255 0 11 21
+9 idle
+";
+        let capture = Capture::read(log.as_bytes()).unwrap();
+
+        assert_eq!(replay(&capture, false, &mut Forward::default(), 255), 20);
+        assert_eq!(replay(&capture, true, &mut Forward::default(), 255), 21);
+        assert_eq!(replay(&capture, true, &mut StopAfter::new(Forward::default(), 1), 255), 20);
     }
 }
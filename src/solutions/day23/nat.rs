@@ -1,6 +1,10 @@
+use std::ops::ControlFlow;
 use std::sync::mpsc::Receiver;
+use crate::solutions::day23::config::IDLE_BEFORE_NAT;
 use crate::solutions::day23::cpu::Network;
 use crate::solutions::day23::deadlock::ActivityCount;
+use crate::solutions::day23::policy::NatPolicy;
+use crate::solutions::day23::shutdown::Shutdown;
 use crate::solutions::day23::Packet;
 
 pub struct NAT {
@@ -9,41 +13,55 @@ pub struct NAT {
     deadlock: Receiver<()>,
     incoming_msgs: Receiver<Packet>,
     network: Network,
+    shutdown: Shutdown,
 }
 
 impl NAT {
-    pub fn new(activity_count: ActivityCount, deadlock: Receiver<()>, incoming_msgs: Receiver<Packet>, network: Network) -> Self {
+    pub fn new(activity_count: ActivityCount, deadlock: Receiver<()>, incoming_msgs: Receiver<Packet>, network: Network, shutdown: Shutdown) -> Self {
         Self {
             activity_count,
             deadlock,
             incoming_msgs,
             network,
+            shutdown,
         }
     }
 
-    pub fn run(mut self) -> i64 {
-        let mut most_recent_packet = None;
-        let mut prev_outgoing_y = None;
+    /// Returns None if the network shut down before we had an answer.
+    pub fn run(mut self, policy: &mut dyn NatPolicy) -> Option<i64> {
+        let mut received_any = false;
 
         loop {
             // Wait for deadlock.
-            self.deadlock.recv().unwrap();
+            self.shutdown.recv(&self.deadlock)?;
 
             // Catch up on recent messages.
             while let Ok(packet) = self.incoming_msgs.try_recv() {
-                most_recent_packet = Some(packet);
+                received_any = true;
+                policy.receive(packet);
             }
-            let packet = most_recent_packet.expect("Deadlocked before NAT received any packets.");
 
-            // Same y twice in a row?
-            if prev_outgoing_y == Some(packet.y) {
-                return packet.y;
+            if !received_any {
+                self.shutdown.fail(IDLE_BEFORE_NAT.to_string());
+                return None;
             }
-            prev_outgoing_y = Some(packet.y);
 
-            // Hey! Wake up!
-            self.activity_count.incr(); // +1 message in flight.
-            self.network.cpus[0].send(packet).unwrap();
+            if let Some(capture) = &self.network.capture {
+                capture.lock().unwrap().push_idle();
+            }
+
+            let packets = match policy.on_idle() {
+                ControlFlow::Continue(packets) => packets,
+                ControlFlow::Break(y) => return Some(y),
+            };
+
+            for (dest, packet) in packets {
+                self.activity_count.incr(); // +1 message in flight.
+                if let Err(msg) = self.network.send(self.network.config.nat_addr, dest as i64, packet) {
+                    self.shutdown.fail(msg);
+                    return None;
+                }
+            }
         }
     }
 }
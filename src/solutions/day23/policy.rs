@@ -0,0 +1,206 @@
+use std::collections::HashSet;
+use std::ops::ControlFlow;
+use crate::solutions::day23::Packet;
+
+/// What the NAT does with the packets it receives, separate from how the network is run.
+pub trait NatPolicy {
+    /// Called for every packet sent to the NAT.
+    fn receive(&mut self, packet: Packet);
+
+    /// Called when the network goes idle. Either wake it up by sending packets to some CPUs,
+    /// or stop with the answer.
+    fn on_idle(&mut self) -> ControlFlow<i64, Vec<(usize, Packet)>>;
+
+    /// Anything worth telling the user once we're done.
+    fn report(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Where `Forward` sends its packets.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Dest {
+    Cpu(usize),
+    /// Every CPU.
+    All { num_cpus: usize },
+}
+
+/// Forward the most recent packet on each wakeup, and stop when the same y is sent twice in a row.
+///
+/// The default, sending to CPU 0, is what the puzzle asks for.
+pub struct Forward {
+    dest: Dest,
+    most_recent_packet: Option<Packet>,
+    prev_outgoing_y: Option<i64>,
+}
+
+impl Forward {
+    pub fn new(dest: Dest) -> Self {
+        Self { dest, most_recent_packet: None, prev_outgoing_y: None }
+    }
+}
+
+impl Default for Forward {
+    fn default() -> Self {
+        Self::new(Dest::Cpu(0))
+    }
+}
+
+impl NatPolicy for Forward {
+    fn receive(&mut self, packet: Packet) {
+        self.most_recent_packet = Some(packet);
+    }
+
+    fn on_idle(&mut self) -> ControlFlow<i64, Vec<(usize, Packet)>> {
+        let packet = self.most_recent_packet.expect("Idle before NAT received any packets.");
+
+        // Same y twice in a row?
+        if self.prev_outgoing_y == Some(packet.y) {
+            return ControlFlow::Break(packet.y);
+        }
+        self.prev_outgoing_y = Some(packet.y);
+
+        // Hey! Wake up!
+        match self.dest {
+            Dest::Cpu(addr) => ControlFlow::Continue(vec![(addr, packet)]),
+            Dest::All { num_cpus } => ControlFlow::Continue((0..num_cpus).map(|addr| (addr, packet)).collect()),
+        }
+    }
+}
+
+/// Stop after `max_wakeups` wakeups (if `inner` hasn't stopped already), with the y value of the
+/// last packet sent.
+pub struct StopAfter<P> {
+    inner: P,
+    max_wakeups: usize,
+    wakeups: usize,
+    last_sent_y: Option<i64>,
+}
+
+impl<P> StopAfter<P> {
+    pub fn new(inner: P, max_wakeups: usize) -> Self {
+        Self { inner, max_wakeups, wakeups: 0, last_sent_y: None }
+    }
+}
+
+impl<P: NatPolicy> NatPolicy for StopAfter<P> {
+    fn receive(&mut self, packet: Packet) {
+        self.inner.receive(packet);
+    }
+
+    fn on_idle(&mut self) -> ControlFlow<i64, Vec<(usize, Packet)>> {
+        if self.wakeups == self.max_wakeups {
+            return ControlFlow::Break(self.last_sent_y.expect("NAT stopped before sending anything."));
+        }
+
+        let packets = self.inner.on_idle()?;
+        self.wakeups += 1;
+        if let Some(&(_, packet)) = packets.last() {
+            self.last_sent_y = Some(packet.y);
+        }
+
+        ControlFlow::Continue(packets)
+    }
+
+    fn report(&self) -> Option<String> {
+        self.inner.report()
+    }
+}
+
+/// Keep track of the NAT's traffic, for `report`.
+pub struct Stats<P> {
+    inner: P,
+    packets_received: usize,
+    wakeups: usize,
+    packets_sent: usize,
+    distinct_ys: HashSet<i64>,
+}
+
+impl<P> Stats<P> {
+    pub fn new(inner: P) -> Self {
+        Self { inner, packets_received: 0, wakeups: 0, packets_sent: 0, distinct_ys: HashSet::new() }
+    }
+}
+
+impl<P: NatPolicy> NatPolicy for Stats<P> {
+    fn receive(&mut self, packet: Packet) {
+        self.packets_received += 1;
+        self.distinct_ys.insert(packet.y);
+        self.inner.receive(packet);
+    }
+
+    fn on_idle(&mut self) -> ControlFlow<i64, Vec<(usize, Packet)>> {
+        let packets = self.inner.on_idle()?;
+        self.wakeups += 1;
+        self.packets_sent += packets.len();
+
+        ControlFlow::Continue(packets)
+    }
+
+    fn report(&self) -> Option<String> {
+        let report = format!(
+            "NAT received {} packets ({} distinct y values), and sent {} packets over {} wakeups.",
+            self.packets_received, self.distinct_ys.len(), self.packets_sent, self.wakeups,
+        );
+
+        match self.inner.report() {
+            Some(inner) => Some(format!("{}\n{}", report, inner)),
+            None => Some(report),
+        }
+    }
+}
+
+/// Lets `main` choose a policy at runtime.
+impl<P: NatPolicy + ?Sized> NatPolicy for Box<P> {
+    fn receive(&mut self, packet: Packet) {
+        (**self).receive(packet);
+    }
+
+    fn on_idle(&mut self) -> ControlFlow<i64, Vec<(usize, Packet)>> {
+        (**self).on_idle()
+    }
+
+    fn report(&self) -> Option<String> {
+        (**self).report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1: Packet = Packet { x: 1, y: 10 };
+    const P2: Packet = Packet { x: 2, y: 20 };
+
+    #[test]
+    fn test_forward() {
+        let mut nat = Forward::default();
+        nat.receive(P1);
+        assert_eq!(nat.on_idle(), ControlFlow::Continue(vec![(0, P1)]));
+        nat.receive(P2);
+        assert_eq!(nat.on_idle(), ControlFlow::Continue(vec![(0, P2)]));
+        assert_eq!(nat.on_idle(), ControlFlow::Break(20));
+
+        let mut nat = Forward::new(Dest::All { num_cpus: 3 });
+        nat.receive(P1);
+        assert_eq!(nat.on_idle(), ControlFlow::Continue(vec![(0, P1), (1, P1), (2, P1)]));
+    }
+
+    #[test]
+    fn test_wrappers() {
+        let mut nat = Stats::new(StopAfter::new(Forward::new(Dest::Cpu(7)), 2));
+
+        nat.receive(P1);
+        assert_eq!(nat.on_idle(), ControlFlow::Continue(vec![(7, P1)]));
+        nat.receive(P2);
+        assert_eq!(nat.on_idle(), ControlFlow::Continue(vec![(7, P2)]));
+        // `Forward` would carry on, since this is a new y.
+        nat.receive(P1);
+        assert_eq!(nat.on_idle(), ControlFlow::Break(20));
+
+        assert_eq!(
+            nat.report().unwrap(),
+            "NAT received 3 packets (2 distinct y values), and sent 2 packets over 2 wakeups.",
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Tells the threaded network to stop, e.g. once we have our answer, or because something
+/// went wrong.
+///
+/// Clone it once per thread.
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    signalled: Arc<AtomicBool>,
+    /// The first error passed to `fail`.
+    error: Arc<Mutex<Option<String>>>,
+}
+
+/// How often `Shutdown::recv` checks whether it should give up.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+impl Shutdown {
+    pub fn signal(&self) {
+        self.signalled.store(true, SeqCst);
+    }
+
+    pub fn is_signalled(&self) -> bool {
+        self.signalled.load(SeqCst)
+    }
+
+    /// Signal, and remember why (unless something else already failed).
+    pub fn fail(&self, msg: String) {
+        self.error.lock().unwrap().get_or_insert(msg);
+        self.signal();
+    }
+
+    /// Whatever was passed to `fail`, if anything.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
+    }
+
+    /// Wait for a message. Returns None if we're shutting down (or the channel hung up) first.
+    pub fn recv<T>(&self, rx: &Receiver<T>) -> Option<T> {
+        loop {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok(msg) => return Some(msg),
+                Err(RecvTimeoutError::Timeout) if !self.is_signalled() => {}
+                Err(_) => return None,
+            }
+        }
+    }
+}
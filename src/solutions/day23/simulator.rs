@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+use crate::intcode_computer::{Event, IntcodeComputer, Snapshot};
+use crate::solutions::day23::Packet;
+use crate::solutions::day23::capture::{Capture, Record};
+
+/// Runs the whole network on one thread, giving each NIC a turn in order.
+///
+/// Each turn runs one NIC until its next input or output, so the results are the same every time.
+pub struct Simulator {
+    nics: Vec<Nic>,
+    /// Turns taken so far.
+    time: u64,
+    capture: Option<Capture>,
+}
+
+struct Nic {
+    computer: IntcodeComputer,
+    inbound_msgs: VecDeque<Packet>,
+    /// Words of an outgoing packet that the NIC hasn't finished writing yet.
+    outbound_packet: Vec<i64>,
+    /// The NIC's state when it last polled its empty queue, if it hasn't sent or received
+    /// anything since.
+    last_empty_poll: Option<Snapshot>,
+    /// Polled its empty queue in exactly the same state as last time. Given the same `-1`, it
+    /// will do the same thing all over again, so it's stuck until it gets a packet.
+    idle: bool,
+}
+
+/// The reason `Simulator::run` returned.
+#[derive(Copy, Clone)]
+pub enum NetEvent {
+    /// A NIC sent a packet to an address outside the network.
+    Sent { src: usize, addr: i64, packet: Packet },
+    /// Every NIC is waiting on an empty queue, so nothing more will happen by itself.
+    Idle,
+}
+
+impl Simulator {
+    pub fn new(nic_program: &[i64], num_cpus: usize) -> Self {
+        let nics = (0..num_cpus).map(|id| {
+            let mut computer = IntcodeComputer::new(nic_program.to_vec());
+
+            // First input instruction is always the CPU's own id.
+            computer.provide_input(id as i64);
+
+            Nic {
+                computer,
+                inbound_msgs: VecDeque::new(),
+                outbound_packet: Vec::with_capacity(3),
+                last_empty_poll: None,
+                idle: false,
+            }
+        }).collect();
+
+        Self { nics, time: 0, capture: None }
+    }
+
+    /// Start recording packets. See `take_capture`.
+    pub fn start_capture(&mut self) {
+        self.capture = Some(Capture::default());
+    }
+
+    /// Everything recorded since `start_capture`.
+    pub fn take_capture(&mut self) -> Option<Capture> {
+        self.capture.take()
+    }
+
+    /// Deliver a packet to a NIC, e.g. from outside the network.
+    pub fn send(&mut self, src: i64, dest: usize, packet: Packet) {
+        self.record(Record::Packet { time: self.time, src, dest: dest as i64, packet });
+        self.nics[dest].inbound_msgs.push_back(packet);
+    }
+
+    fn record(&mut self, record: Record) {
+        if let Some(capture) = &mut self.capture {
+            capture.records.push(record);
+        }
+    }
+
+    /// Run until a packet leaves the network, or the network goes idle.
+    pub fn run(&mut self) -> NetEvent {
+        loop {
+            for id in 0..self.nics.len() {
+                self.time += 1;
+
+                if let Some((addr, packet)) = self.take_turn(id) {
+                    if 0 <= addr && addr < self.nics.len() as i64 {
+                        self.send(id as i64, addr as usize, packet);
+                    } else {
+                        self.record(Record::Packet { time: self.time, src: id as i64, dest: addr, packet });
+                        return NetEvent::Sent { src: id, addr, packet };
+                    }
+                }
+            }
+
+            if self.nics.iter().all(|nic| nic.idle && nic.inbound_msgs.is_empty()) {
+                self.record(Record::Idle { time: self.time });
+                return NetEvent::Idle;
+            }
+        }
+    }
+
+    /// Run one NIC until its next input or output. Returns the packet it sent, if it finished one.
+    fn take_turn(&mut self, id: usize) -> Option<(i64, Packet)> {
+        let nic = &mut self.nics[id];
+
+        match nic.computer.run_until_event().unwrap() {
+            Event::NeedsInput => match nic.inbound_msgs.pop_front() {
+                Some(packet) => {
+                    nic.last_empty_poll = None;
+                    nic.idle = false;
+
+                    nic.computer.provide_input(packet.x);
+                    nic.computer.provide_input(packet.y);
+                }
+                None => {
+                    // Once idle, it stays that way until it gets a packet, so no need to check again.
+                    if !nic.idle {
+                        match &nic.last_empty_poll {
+                            Some(state) if nic.computer.is_in_state(state) => nic.idle = true,
+                            _ => nic.last_empty_poll = Some(nic.computer.snapshot()),
+                        }
+                    }
+
+                    nic.computer.provide_input(-1);
+                }
+            }
+            Event::Output(val) => {
+                nic.last_empty_poll = None;
+                nic.idle = false;
+                nic.outbound_packet.push(val);
+
+                // Finished packet; send it.
+                if let [addr, x, y] = nic.outbound_packet[..] {
+                    nic.outbound_packet.clear();
+                    return Some((addr, Packet { x, y }));
+                }
+            }
+            Event::Halted => panic!("CPU {} halted", id),
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+    use super::*;
+    use crate::intcode_computer::{assemble, read_intcode_program};
+    use crate::solutions::day23::config::Config;
+
+    #[test]
+    fn test_deterministic() {
+        let file = File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/inputs/23")).unwrap();
+        let prog = read_intcode_program(BufReader::new(file));
+
+        // Everything the network does up to the third time it goes idle, with the NAT's
+        // usual wakeups.
+        let config = Config::default();
+        let history = || {
+            let mut network = Simulator::new(&prog, config.num_cpus);
+            let mut history: Vec<Option<(i64, i64, i64)>> = vec![];
+
+            while history.iter().filter(|event| event.is_none()).count() < 3 {
+                match network.run() {
+                    NetEvent::Sent { addr, packet, .. } => history.push(Some((addr, packet.x, packet.y))),
+                    NetEvent::Idle => {
+                        let &(_, x, y) = history.iter().rev().flatten().next().unwrap();
+                        network.send(config.nat_addr, 0, Packet { x, y });
+                        history.push(None);
+                    }
+                }
+            }
+
+            history
+        };
+
+        let first = history();
+        assert!(first.len() > 3);
+        assert_eq!(first, history());
+    }
+
+    #[test]
+    fn test_slow_sender() {
+        // Sends a packet to the NAT only after several empty polls, then just keeps polling.
+        let prog = assemble("
+                    in [id]
+            wait:   in [x]
+                    add [polls], #1, [polls]
+                    lt [polls], #5, [waiting]
+                    jt [waiting], #wait
+                    out #255
+                    out #1
+                    out #2
+            done:   in [x]
+                    jt #1, #done
+            id:     data 0
+            x:      data 0
+            polls:  data 0
+            waiting: data 0
+        ").unwrap();
+
+        let mut network = Simulator::new(&prog, 2);
+        assert!(matches!(network.run(), NetEvent::Sent { src: 0, addr: 255, packet: Packet { x: 1, y: 2 } }));
+        assert!(matches!(network.run(), NetEvent::Sent { src: 1, addr: 255, packet: Packet { x: 1, y: 2 } }));
+        assert!(matches!(network.run(), NetEvent::Idle));
+    }
+}
@@ -1,4 +1,4 @@
-use std::io;
+use crate::solutions::{Answer, Solution};
 use crate::solutions::day24::input::read_input;
 
 mod input;
@@ -19,9 +19,15 @@ fn should_live(is_bug: bool, num_adj: usize) -> bool {
     }
 }
 
-pub fn main() {
-    let grid = read_input(io::stdin().lock());
+#[derive(Default)]
+pub struct Day24;
 
-    println!("{}", part_1::solve(grid.clone()));
-    println!("{}", part_2::solve(grid));
+impl Solution for Day24 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1::solve(read_input(input.as_bytes())).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        part_2::solve(read_input(input.as_bytes())).into()
+    }
 }
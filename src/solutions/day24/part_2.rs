@@ -65,7 +65,7 @@ impl Planet {
 
     fn should_live(&self, p: Point) -> bool {
         let is_bug = self.bugs.contains(&p);
-        let num_adj = Self::neighbors(p).filter(|p2| self.bugs.contains(&p2)).count();
+        let num_adj = Self::neighbors(p).filter(|p2| self.bugs.contains(p2)).count();
 
         should_live(is_bug, num_adj)
     }
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use crate::solutions::{Answer, Solution};
+use crate::solutions::geometry::{Dir, Point};
+use crate::solutions::geometry::Dir::{Down, Left, Right, Up};
+
+/// Each segment of a wire goes some distance in one direction.
+type Path = Vec<(Dir, usize)>;
+
+fn read_input(input: &str) -> (Path, Path) {
+    let mut lines = input.lines().map(read_path);
+    let paths = (lines.next().unwrap(), lines.next().unwrap());
+    assert!(lines.next().is_none(), "Expected exactly 2 wires.");
+
+    paths
+}
+
+/// E.g. `R8,U5,L5,D3`.
+fn read_path(line: &str) -> Path {
+    line.split(',').map(|segment| {
+        let dir = match segment.chars().next() {
+            Some('U') => Up,
+            Some('D') => Down,
+            Some('L') => Left,
+            Some('R') => Right,
+            _ => panic!("Invalid segment: {}", segment),
+        };
+
+        (dir, segment[1..].parse().unwrap())
+    }).collect()
+}
+
+#[derive(Default)]
+pub struct Day3;
+
+impl Solution for Day3 {
+    fn part_1(&self, input: &str) -> Answer {
+        let (a, b) = read_input(input);
+        part_1(&a, &b).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        let (a, b) = read_input(input);
+        part_2(&a, &b).into()
+    }
+}
+
+/// Manhattan distance from the central port to the closest intersection.
+fn part_1(a: &Path, b: &Path) -> isize {
+    intersections(a, b).map(|(p, _)| p.row.abs() + p.col.abs()).min().expect("The wires don't cross.")
+}
+
+/// Fewest combined steps the wires take to reach an intersection.
+fn part_2(a: &Path, b: &Path) -> usize {
+    intersections(a, b).map(|(_, steps)| steps).min().expect("The wires don't cross.")
+}
+
+/// Every point where the wires cross, and the combined steps they take to first get there.
+fn intersections(a: &Path, b: &Path) -> impl Iterator<Item=(Point, usize)> {
+    let a = visits(a);
+    let b = visits(b);
+
+    a.into_iter().filter_map(move |(p, a_steps)| {
+        b.get(&p).map(|b_steps| (p, a_steps + b_steps))
+    })
+}
+
+/// Every point that a wire visits, besides the central port, and the number of steps it takes
+/// to first get there.
+fn visits(path: &Path) -> HashMap<Point, usize> {
+    let mut visits = HashMap::new();
+    let mut pos = Point { row: 0, col: 0 };
+    let mut steps = 0;
+
+    for &(dir, dist) in path {
+        for _ in 0..dist {
+            pos = pos + dir.to_point();
+            steps += 1;
+
+            visits.entry(pos).or_insert(steps);
+        }
+    }
+
+    visits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLES: [(&str, isize, usize); 3] = [
+        ("R8,U5,L5,D3\nU7,R6,D4,L4\n", 6, 30),
+        ("R75,D30,R83,U83,L12,D49,R71,U7,L72\nU62,R66,U55,R34,D71,R55,D58,R83\n", 159, 610),
+        ("R98,U47,R26,D63,R33,U87,L62,D20,R33,U53,R51\nU98,R91,D20,R16,D67,R40,U7,R15,U6,R7\n", 135, 410),
+    ];
+
+    #[test]
+    fn test_examples() {
+        for (input, dist, steps) in EXAMPLES {
+            let (a, b) = read_input(input);
+
+            assert_eq!(part_1(&a, &b), dist);
+            assert_eq!(part_2(&a, &b), steps);
+        }
+    }
+}
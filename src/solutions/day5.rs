@@ -1,24 +1,41 @@
-use crate::intcode_computer::{read_intcode_program, IntcodeComputer};
-use std::io;
+use crate::intcode_computer::{read_intcode_program, input_from_iter, output_to_vec, IntcodeComputer};
+use crate::solutions::{Answer, Solution};
 
-pub fn main() {
-    let prog = read_intcode_program(io::stdin().lock());
-    part_1(prog.clone());
-    part_2(prog);
+#[derive(Default)]
+pub struct Day5;
+
+impl Solution for Day5 {
+    fn part_1(&self, input: &str) -> Answer {
+        part_1(read_intcode_program(input.as_bytes())).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        part_2(read_intcode_program(input.as_bytes())).into()
+    }
 }
 
-fn part_1(prog: Vec<i64>) {
-    IntcodeComputer::new(prog).io(
-        || 1,
-        |x| if x != 0 {
-            println!("{}", x)
-        },
-    ).run();
+/// Returns the diagnostic code.
+fn part_1(prog: Vec<i64>) -> i64 {
+    let outputs = run(prog, 1);
+    let (&code, tests) = outputs.split_last().unwrap();
+
+    // Every test before the diagnostic code should output 0 (i.e. pass).
+    assert!(tests.iter().all(|&x| x == 0), "Failed tests: {:?}", tests);
+    code
 }
 
-fn part_2(prog: Vec<i64>) {
+fn part_2(prog: Vec<i64>) -> i64 {
+    let outputs = run(prog, 5);
+    assert_eq!(outputs.len(), 1);
+    outputs[0]
+}
+
+fn run(prog: Vec<i64>, system_id: i64) -> Vec<i64> {
+    let mut outputs = vec![];
     IntcodeComputer::new(prog).io(
-        || 5,
-        |x| println!("{}", x),
-    ).run();
+        input_from_iter([system_id]),
+        output_to_vec(&mut outputs),
+    ).run().unwrap();
+
+    outputs
 }
@@ -1,15 +1,26 @@
-use std::io;
-use crate::intcode_computer::{IntcodeComputer, read_intcode_program};
+use crate::intcode_computer::{IntcodeComputer, input_from_iter, output_to_vec, read_intcode_program};
+use crate::solutions::{Answer, Solution};
 
-pub fn main() {
-    let prog = read_intcode_program(io::stdin().lock());
+#[derive(Default)]
+pub struct Day9;
 
-    solve(1, prog.clone());
-    solve(2, prog);
+impl Solution for Day9 {
+    fn part_1(&self, input: &str) -> Answer {
+        solve(1, read_intcode_program(input.as_bytes())).into()
+    }
+
+    fn part_2(&self, input: &str) -> Answer {
+        solve(2, read_intcode_program(input.as_bytes())).into()
+    }
 }
 
-fn solve(part_number: i64, prog: Vec<i64>) {
+fn solve(part_number: i64, prog: Vec<i64>) -> i64 {
     assert!(part_number == 1 || part_number == 2);
 
-    IntcodeComputer::new(prog).io(move || part_number, |x| println!("{}", x)).run();
+    let mut outputs = vec![];
+    IntcodeComputer::new(prog).io(input_from_iter([part_number]), output_to_vec(&mut outputs)).run().unwrap();
+
+    // Anything more would be opcodes that the BOOST program thinks are broken.
+    assert_eq!(outputs.len(), 1, "Unexpected output: {:?}", outputs);
+    outputs[0]
 }
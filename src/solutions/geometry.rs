@@ -1,7 +1,8 @@
 use std::ops::Add;
-use crate::solutions::day17::map::geometry::Dir::{Down, Left, Right, Up};
+use crate::solutions::geometry::Dir::{Down, Left, Right, Up};
 
-#[derive(Debug, Copy, Clone)]
+/// Rows go down, and columns go right.
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
 pub struct Point {
     pub row: isize,
     pub col: isize,
@@ -0,0 +1,66 @@
+//! Checks every solved day against its puzzle input in `inputs/`, and the answers in
+//! `tests/answers.txt`, so that we notice if a change breaks one.
+
+use std::collections::BTreeMap;
+use std::fs;
+use advent_2019::{days, solution};
+
+const ANSWERS: &str = include_str!("answers.txt");
+
+/// (day, part) -> answer.
+fn expected_answers() -> BTreeMap<(u32, u32), &'static str> {
+    ANSWERS.lines().filter(|line| !line.starts_with('#') && !line.trim().is_empty()).map(|line| {
+        match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [day, part, answer] => ((day.parse().unwrap(), part.parse().unwrap()), answer),
+            _ => panic!("Invalid line in answers.txt: {}", line),
+        }
+    }).collect()
+}
+
+fn check(day: u32) {
+    let path = format!("{}/inputs/{}", env!("CARGO_MANIFEST_DIR"), day);
+    let input = fs::read_to_string(path).unwrap();
+    let solution = solution(day).unwrap();
+    let expected = expected_answers();
+
+    assert_eq!(solution.part_1(&input).to_string(), expected[&(day, 1)], "Day {} part 1", day);
+    assert_eq!(solution.part_2(&input).to_string(), expected[&(day, 2)], "Day {} part 2", day);
+}
+
+/// One test per day, so they run in parallel, and failures say which day broke.
+macro_rules! tests {
+    ($($name:ident: $day:expr,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                check($day);
+            }
+        )*
+
+        const TESTED: &[u32] = &[$($day),*];
+    };
+}
+
+tests! {
+    day1: 1,
+    day2: 2,
+    day3: 3,
+    day5: 5,
+    day9: 9,
+    day17: 17,
+    day19: 19,
+    day21: 21,
+    day22: 22,
+    day23: 23,
+    day24: 24,
+}
+
+#[test]
+fn every_day_covered() {
+    let solved: Vec<_> = days().collect();
+    assert_eq!(solved, TESTED, "Add any new days to `tests!`");
+
+    let answered: Vec<_> = expected_answers().keys().map(|&(day, _)| day).collect();
+    let both_parts: Vec<_> = solved.iter().flat_map(|&day| [day, day]).collect();
+    assert_eq!(answered, both_parts, "Every day needs both answers in answers.txt");
+}